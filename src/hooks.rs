@@ -1,6 +1,7 @@
 pub mod extract;
 pub mod init;
 pub mod install;
+pub mod lazy;
 pub mod load;
 pub mod make;
 pub mod resource;
@@ -1,72 +1,1015 @@
-use infer;
-use std::path::Path;
+use log::info;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
 
 use crate::utils::{script::*, shells::SupportedShell};
 
 pub fn extract(
     current_shell: &SupportedShell,
     cmd: &str,
+    current_dir: &Path,
+) -> Result<ShellOutput, Box<dyn std::error::Error>> {
+    run_cmd_in_shell_with_output(current_shell, cmd, current_dir)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Tar(Compression),
+    Zip,
+    SevenZip,
+    Compressed(Compression), // single compressed file, a binary or a tarball
+    Deb,
+    Rpm,
+    AppImage,
+    Dmg,
+}
+
+// formats by file name suffix, tarballs ahead of the bare compression suffixes
+const ARCHIVE_SUFFIXES: [(&str, ArchiveFormat); 20] = [
+    (".tar.gz", ArchiveFormat::Tar(Compression::Gzip)),
+    (".tgz", ArchiveFormat::Tar(Compression::Gzip)),
+    (".tar.xz", ArchiveFormat::Tar(Compression::Xz)),
+    (".txz", ArchiveFormat::Tar(Compression::Xz)),
+    (".tar.bz2", ArchiveFormat::Tar(Compression::Bzip2)),
+    (".tbz2", ArchiveFormat::Tar(Compression::Bzip2)),
+    (".tbz", ArchiveFormat::Tar(Compression::Bzip2)),
+    (".tar.zst", ArchiveFormat::Tar(Compression::Zstd)),
+    (".tzst", ArchiveFormat::Tar(Compression::Zstd)),
+    (".tar", ArchiveFormat::Tar(Compression::None)),
+    (".zip", ArchiveFormat::Zip),
+    (".7z", ArchiveFormat::SevenZip),
+    (".gz", ArchiveFormat::Compressed(Compression::Gzip)),
+    (".xz", ArchiveFormat::Compressed(Compression::Xz)),
+    (".bz2", ArchiveFormat::Compressed(Compression::Bzip2)),
+    (".zst", ArchiveFormat::Compressed(Compression::Zstd)),
+    (".deb", ArchiveFormat::Deb),
+    (".rpm", ArchiveFormat::Rpm),
+    (".appimage", ArchiveFormat::AppImage),
+    (".dmg", ArchiveFormat::Dmg),
+];
+
+fn get_archive_format_by_name(name: &str) -> Option<ArchiveFormat> {
+    let name = name.to_lowercase();
+    ARCHIVE_SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.ends_with(suffix))
+        .map(|(_, format)| *format)
+}
+
+// format by file name, otherwise by content for assets downloaded without an extension
+fn get_archive_format(
+    asset_path: &Path,
+) -> Result<Option<ArchiveFormat>, Box<dyn std::error::Error>> {
+    let name = asset_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Some(format) = get_archive_format_by_name(&name) {
+        return Ok(Some(format));
+    }
+
+    // type 2 AppImages are ELF binaries marked with AI\x02 in the ELF padding
+    let mut magic = [0u8; 11];
+    let is_appimage = File::open(asset_path)?
+        .read_exact(&mut magic)
+        .is_ok_and(|_| magic.starts_with(b"\x7fELF") && &magic[8..] == b"AI\x02");
+    if is_appimage {
+        return Ok(Some(ArchiveFormat::AppImage));
+    }
+
+    Ok(
+        infer::get_from_path(asset_path)?.and_then(|kind| match kind.extension() {
+            "tar" => Some(ArchiveFormat::Tar(Compression::None)),
+            "zip" => Some(ArchiveFormat::Zip),
+            "7z" => Some(ArchiveFormat::SevenZip),
+            "gz" => Some(ArchiveFormat::Compressed(Compression::Gzip)),
+            "xz" => Some(ArchiveFormat::Compressed(Compression::Xz)),
+            "bz2" => Some(ArchiveFormat::Compressed(Compression::Bzip2)),
+            "zst" => Some(ArchiveFormat::Compressed(Compression::Zstd)),
+            "deb" => Some(ArchiveFormat::Deb),
+            "rpm" => Some(ArchiveFormat::Rpm),
+            _ => None,
+        }),
+    )
+}
+
+// compression of a stream by its magic bytes, as rpm payloads carry no name
+fn get_compression(magic: &[u8]) -> Option<Compression> {
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Some(Compression::Gzip)
+    } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Some(Compression::Xz)
+    } else if magic.starts_with(b"BZh") {
+        Some(Compression::Bzip2)
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(Compression::Zstd)
+    } else if magic.starts_with(b"07070") {
+        Some(Compression::None)
+    } else {
+        None
+    }
+}
+
+fn get_decoder<'a>(
+    reader: impl Read + 'a,
+    compression: Compression,
+) -> Result<Box<dyn Read + 'a>, Box<dyn std::error::Error>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+    })
+}
+
+// path of an entry relative to the install dir with the leading components stripped, none
+// if nothing is left; absolute paths and paths with `..` are refused
+fn get_entry_path(
+    path: &Path,
+    strip_components: usize,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let mut components = vec![];
+    for component in path.components() {
+        match component {
+            Component::Normal(component) => components.push(component),
+            Component::CurDir => {}
+            _ => {
+                return Err(format!(
+                    "archive entry {} points outside of the install dir",
+                    path.display()
+                )
+                .into())
+            }
+        }
+    }
+
+    if components.len() <= strip_components {
+        return Ok(None);
+    }
+
+    Ok(Some(components[strip_components..].iter().collect()))
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+// symlinks have to resolve within the install dir, starting from the dir of the link;
+// the target may neither pass through a symlink extracted before it, nor climb out of
+// a dir that does not exist yet, as a later symlink could take its place
+fn check_link_target(
+    dest_dir: &Path,
+    entry_path: &Path,
+    target: &Path,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    run_cmd_in_shell(current_shell, cmd)?;
+    let escape_err = || {
+        format!(
+            "archive symlink {} -> {} points outside of the install dir",
+            entry_path.display(),
+            target.display()
+        )
+    };
+
+    let mut resolved = entry_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut components = target.components().peekable();
+    while let Some(component) = components.next() {
+        match component {
+            Component::Normal(component) => {
+                resolved.push(component);
+                if components.peek().is_some() && is_symlink(&dest_dir.join(&resolved)) {
+                    return Err(format!(
+                        "archive symlink {} -> {} passes through a symlink",
+                        entry_path.display(),
+                        target.display()
+                    )
+                    .into());
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let dir = dest_dir.join(&resolved);
+                if resolved.as_os_str().is_empty() || is_symlink(&dir) || !dir.is_dir() {
+                    return Err(escape_err().into());
+                }
+                resolved.pop();
+            }
+            _ => return Err(escape_err().into()),
+        }
+    }
 
     Ok(())
 }
 
-pub fn extract_asset(
-    current_shell: &SupportedShell,
+// hard links may not name a symlink, nor reach their target through one
+fn check_hard_link_target(
+    dest_dir: &Path,
+    entry_path: &Path,
+    target_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut resolved = dest_dir.to_path_buf();
+    for component in target_path.components() {
+        resolved.push(component);
+        if is_symlink(&resolved) {
+            return Err(format!(
+                "archive hard link {} -> {} passes through a symlink",
+                entry_path.display(),
+                target_path.display()
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+// destination of an entry, refusing to write through symlinks extracted before it
+fn get_entry_dest(
+    dest_dir: &Path,
+    entry_path: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut dest = dest_dir.to_path_buf();
+    if let Some(parent) = entry_path.parent() {
+        for component in parent.components() {
+            dest.push(component);
+            if is_symlink(&dest) {
+                return Err(format!(
+                    "archive entry {} is placed through a symlink",
+                    entry_path.display()
+                )
+                .into());
+            }
+        }
+        fs::create_dir_all(&dest)?;
+    }
+    dest.push(entry_path.file_name().unwrap_or_default());
+
+    // replace rather than follow an existing symlink
+    if is_symlink(&dest) {
+        fs::remove_file(&dest)?;
+    }
+
+    Ok(dest)
+}
+
+fn create_symlink(target: &Path, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, dest)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(target, dest)?;
+
+    Ok(())
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+
+    Ok(())
+}
+
+fn extract_tar(
+    reader: impl Read,
+    dest_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = match get_entry_path(&entry.path()?, strip_components)? {
+            Some(entry_path) => entry_path,
+            None => continue,
+        };
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_character_special()
+            || entry_type.is_block_special()
+            || entry_type.is_fifo()
+        {
+            continue;
+        }
+
+        let link_name = entry.link_name()?.map(|link_name| link_name.to_path_buf());
+        let dest = get_entry_dest(dest_dir, &entry_path)?;
+        if entry_type.is_symlink() {
+            check_link_target(dest_dir, &entry_path, &link_name.unwrap_or_default())?;
+        } else if entry_type.is_hard_link() {
+            // hard links name another entry of the archive, stripped alike
+            let target = link_name.unwrap_or_default();
+            let target_path = get_entry_path(&target, strip_components)?.ok_or_else(|| {
+                format!(
+                    "archive hard link {} -> {} points outside of the install dir",
+                    entry_path.display(),
+                    target.display()
+                )
+            })?;
+            check_hard_link_target(dest_dir, &entry_path, &target_path)?;
+            if dest.exists() {
+                fs::remove_file(&dest)?;
+            }
+            fs::hard_link(dest_dir.join(target_path), dest)?;
+            continue;
+        }
+
+        entry.unpack(dest)?;
+    }
+
+    Ok(())
+}
+
+fn extract_zip(
     asset_path: &Path,
+    dest_dir: &Path,
+    strip_components: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let asset_path_string = &asset_path.display().to_string();
-    let infer_kind = infer::get_from_path(asset_path)?;
-    if let Some(kind) = &infer_kind {
-        match kind.extension() {
-            "zip" => {
-                run_cmd_in_shell(current_shell, &format!("unzip {}", asset_path_string))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(File::open(asset_path)?))?;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let enclosed_name = file.enclosed_name().ok_or_else(|| {
+            format!(
+                "archive entry {} points outside of the install dir",
+                file.name()
+            )
+        })?;
+        let entry_path = match get_entry_path(&enclosed_name, strip_components)? {
+            Some(entry_path) => entry_path,
+            None => continue,
+        };
+        let dest = get_entry_dest(dest_dir, &entry_path)?;
+
+        if file.is_dir() {
+            fs::create_dir_all(&dest)?;
+        } else if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            check_link_target(dest_dir, &entry_path, Path::new(&target))?;
+            create_symlink(Path::new(&target), &dest)?;
+        } else {
+            io::copy(&mut file, &mut File::create(&dest)?)?;
+            if let Some(mode) = file.unix_mode() {
+                set_mode(&dest, mode)?;
             }
-            "gz" => {
-                run_cmd_in_shell(current_shell, &format!("tar xvf {}", asset_path_string))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_7z(
+    asset_path: &Path,
+    dest_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = sevenz_rust::SevenZReader::open(asset_path, sevenz_rust::Password::empty())?;
+    let extract_entry = |entry: &sevenz_rust::SevenZArchiveEntry,
+                         reader: &mut dyn Read|
+     -> Result<(), Box<dyn std::error::Error>> {
+        let entry_path = match get_entry_path(Path::new(entry.name()), strip_components)? {
+            Some(entry_path) => entry_path,
+            None => return Ok(()),
+        };
+        let dest = get_entry_dest(dest_dir, &entry_path)?;
+        if entry.is_directory() {
+            fs::create_dir_all(&dest)?;
+        } else {
+            io::copy(reader, &mut File::create(&dest)?)?;
+        }
+
+        Ok(())
+    };
+
+    archive.for_each_entries(|entry, reader| {
+        extract_entry(entry, reader).map_err(|err| sevenz_rust::Error::other(err.to_string()))?;
+        // entries share a stream, so skipped ones are read through
+        io::copy(reader, &mut io::sink())?;
+        Ok(true)
+    })?;
+
+    Ok(())
+}
+
+// single compressed file, extracted in turn when it holds a tarball
+fn extract_compressed(
+    asset_path: &Path,
+    dest_dir: &Path,
+    compression: Compression,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file_stem = asset_path
+        .file_stem()
+        .ok_or_else(|| format!("invalid asset path {}", asset_path.display()))?;
+    let dest = get_entry_dest(dest_dir, Path::new(file_stem))?;
+    io::copy(
+        &mut get_decoder(BufReader::new(File::open(asset_path)?), compression)?,
+        &mut File::create(&dest)?,
+    )?;
+
+    if infer::get_from_path(&dest)?.is_some_and(|kind| kind.extension() == "tar") {
+        extract_tar(
+            BufReader::new(File::open(&dest)?),
+            dest_dir,
+            strip_components,
+        )?;
+        fs::remove_file(&dest)?;
+    } else {
+        set_mode(&dest, 0o755)?;
+    }
+
+    Ok(())
+}
+
+fn extract_deb(
+    asset_path: &Path,
+    dest_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut archive = ar::Archive::new(BufReader::new(File::open(asset_path)?));
+    while let Some(entry) = archive.next_entry() {
+        let entry = entry?;
+        // installed files only, leaving out the control.tar of maintainer scripts
+        let name = String::from_utf8_lossy(entry.header().identifier()).to_string();
+        let name = name.trim_end_matches('/');
+        if !name.starts_with("data.tar") {
+            continue;
+        }
+
+        return match get_archive_format_by_name(name) {
+            Some(ArchiveFormat::Tar(compression)) => {
+                extract_tar(get_decoder(entry, compression)?, dest_dir, strip_components)
             }
-            "deb" => {
-                run_cmd_in_shell(
-                    current_shell,
-                    &format!(
-                        "ar xv {}; ls *.tar.* | xargs -n 1 tar xvf",
-                        asset_path_string
-                    ),
-                )?;
+            _ => Err(format!("unsupported deb member {}", name).into()),
+        };
+    }
+
+    Err(format!("no data.tar found in {}", asset_path.display()).into())
+}
+
+// entries of a newc cpio archive, as found in rpm payloads
+fn extract_cpio(
+    mut reader: impl Read,
+    dest_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // hard linked files carry their data on the last of their links only
+    let mut hard_links: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+    loop {
+        let mut header = [0u8; 110];
+        reader.read_exact(&mut header)?;
+        if !header.starts_with(b"07070") {
+            return Err("unsupported cpio format, only newc is".into());
+        }
+        let field = |index: usize| -> Result<u64, Box<dyn std::error::Error>> {
+            let start = 6 + index * 8;
+            Ok(u64::from_str_radix(
+                std::str::from_utf8(&header[start..start + 8])?,
+                16,
+            )?)
+        };
+        let (ino, mode, nlink, file_size, name_size) =
+            (field(0)?, field(1)?, field(4)?, field(6)?, field(11)?);
+
+        // header and name are padded to 4 bytes, as is the data
+        let mut name = vec![0u8; name_size as usize];
+        reader.read_exact(&mut name)?;
+        io::copy(
+            &mut reader.by_ref().take((4 - (110 + name_size) % 4) % 4),
+            &mut io::sink(),
+        )?;
+        let name = String::from_utf8_lossy(&name)
+            .trim_end_matches('\0')
+            .to_string();
+        if name == "TRAILER!!!" {
+            return Ok(());
+        }
+
+        let mut data = reader.by_ref().take(file_size);
+        if let Some(entry_path) = get_entry_path(Path::new(&name), strip_components)? {
+            let dest = get_entry_dest(dest_dir, &entry_path)?;
+            match mode & 0o170000 {
+                0o040000 => fs::create_dir_all(&dest)?,
+                0o120000 => {
+                    let mut target = String::new();
+                    data.read_to_string(&mut target)?;
+                    check_link_target(dest_dir, &entry_path, Path::new(&target))?;
+                    create_symlink(Path::new(&target), &dest)?;
+                }
+                0o100000 if nlink > 1 && file_size == 0 => {
+                    hard_links.entry(ino).or_default().push(dest)
+                }
+                0o100000 => {
+                    io::copy(&mut data, &mut File::create(&dest)?)?;
+                    set_mode(&dest, mode as u32)?;
+                    for link in hard_links.remove(&ino).unwrap_or_default() {
+                        fs::hard_link(&dest, link)?;
+                    }
+                }
+                _ => {}
             }
-            _ => {}
-        }
-    } else if let Some(ext) = &asset_path.extension() {
-        println!("ext {:?}", ext);
-        match ext.to_str().unwrap() {
-            "dmg" => {
-                run_cmd_in_shell(
-                    current_shell,
-                    &format!(
-                        r#"
-                    _extract_dmg() {{
-                      local dmg_name="{}"
-                      echo "dmg_name $dmg_name"
-
-                      local attached_vol_info=$(eval "hdiutil attach $(realpath -m $dmg_name)" | tail -n1)
-                      local attached_vol=$(echo $attached_vol_info | awk -F " " '{{print $1}}')
-                      local attached_vol_mnt_pt=$(echo $attached_vol_info | awk -F " " '{{print $3}}')
-                      cp -R $(realpath -m $attached_vol_mnt_pt)/ .
-                      echo "attached vol $(realpath -m $attached_vol)"
-                      eval "hdiutil detach \"$attached_vol\""
-                    }}
-
-                    _extract_dmg
-
-                    "#,
-                        asset_path_string
-                    ),
-                )?;
+        }
+        io::copy(&mut data, &mut io::sink())?;
+        io::copy(
+            &mut reader.by_ref().take((4 - file_size % 4) % 4),
+            &mut io::sink(),
+        )?;
+    }
+}
+
+// size of an rpm header structure: magic, index entries of 16 bytes each, then the data
+fn read_rpm_header_size(reader: &mut impl Read) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut intro = [0u8; 16];
+    reader.read_exact(&mut intro)?;
+    if !intro.starts_with(&[0x8e, 0xad, 0xe8]) {
+        return Err("invalid rpm header".into());
+    }
+    let index_count = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]) as u64;
+    let data_size = u32::from_be_bytes([intro[12], intro[13], intro[14], intro[15]]) as u64;
+
+    Ok(16 + index_count * 16 + data_size)
+}
+
+// rpms are a lead, a signature and a header, followed by a compressed cpio payload
+fn extract_rpm(
+    asset_path: &Path,
+    dest_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(asset_path)?);
+    let mut lead = [0u8; 96];
+    reader.read_exact(&mut lead)?;
+    if !lead.starts_with(&[0xed, 0xab, 0xee, 0xdb]) {
+        return Err(format!("{} is not an rpm", asset_path.display()).into());
+    }
+
+    // the signature is padded to 8 bytes, unlike the header
+    let signature_size = read_rpm_header_size(&mut reader)?;
+    io::copy(
+        &mut reader.by_ref().take(signature_size.div_ceil(8) * 8 - 16),
+        &mut io::sink(),
+    )?;
+    let header_size = read_rpm_header_size(&mut reader)?;
+    io::copy(&mut reader.by_ref().take(header_size - 16), &mut io::sink())?;
+
+    let compression = get_compression(reader.fill_buf()?).ok_or_else(|| {
+        format!(
+            "unsupported payload compression in {}",
+            asset_path.display()
+        )
+    })?;
+    extract_cpio(
+        get_decoder(reader, compression)?,
+        dest_dir,
+        strip_components,
+    )
+}
+
+// binary the AppImage's desktop entry launches, such as nvim for `Exec=nvim %F`
+fn get_desktop_exec_name(desktop_entry: &str) -> Option<String> {
+    let exec = desktop_entry
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Exec="))?;
+    let program = exec.split_whitespace().next()?.trim_matches('"');
+
+    Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+// AppImages extract themselves into squashfs-root, launched through a wrapper named after
+// the app so `exec` can refer to it
+fn extract_appimage(asset_path: &Path, dest_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    set_mode(asset_path, 0o755)?;
+    let output = Command::new(fs::canonicalize(asset_path)?)
+        .arg("--appimage-extract")
+        .current_dir(dest_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "unable to extract {}: {}",
+            asset_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    let app_dir = dest_dir.join("squashfs-root");
+    let exec_name = fs::read_dir(&app_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|desktop_entry| get_desktop_exec_name(&desktop_entry))
+        .or_else(|| {
+            asset_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })
+        .ok_or_else(|| format!("unable to name the app of {}", asset_path.display()))?;
+
+    let wrapper_path = dest_dir.join(&exec_name);
+    if wrapper_path.exists() {
+        return Ok(());
+    }
+    fs::write(
+        &wrapper_path,
+        "#!/bin/sh\nexec \"$(dirname \"$0\")/squashfs-root/AppRun\" \"$@\"\n",
+    )?;
+    set_mode(&wrapper_path, 0o755)
+}
+
+pub fn extract_asset(
+    current_shell: &SupportedShell,
+    asset_path: &Path,
+    current_dir: &Path,
+    strip_components: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = match get_archive_format(asset_path)? {
+        Some(format) => format,
+        None => return Ok(()),
+    };
+    info!("extracting {} as {:?}", asset_path.display(), format);
+
+    match format {
+        ArchiveFormat::Tar(compression) => extract_tar(
+            get_decoder(BufReader::new(File::open(asset_path)?), compression)?,
+            current_dir,
+            strip_components,
+        ),
+        ArchiveFormat::Zip => extract_zip(asset_path, current_dir, strip_components),
+        ArchiveFormat::SevenZip => extract_7z(asset_path, current_dir, strip_components),
+        ArchiveFormat::Compressed(compression) => {
+            extract_compressed(asset_path, current_dir, compression, strip_components)
+        }
+        ArchiveFormat::Deb => extract_deb(asset_path, current_dir, strip_components),
+        ArchiveFormat::Rpm => extract_rpm(asset_path, current_dir, strip_components),
+        ArchiveFormat::AppImage => extract_appimage(asset_path, current_dir),
+        ArchiveFormat::Dmg => {
+            run_cmd_in_shell_in_dir(
+                current_shell,
+                &format!(
+                    r#"
+                _extract_dmg() {{
+                  local dmg_name="{}"
+                  echo "dmg_name $dmg_name"
+
+                  local attached_vol_info=$(eval "hdiutil attach $(realpath -m $dmg_name)" | tail -n1)
+                  local attached_vol=$(echo $attached_vol_info | awk -F " " '{{print $1}}')
+                  local attached_vol_mnt_pt=$(echo $attached_vol_info | awk -F " " '{{print $3}}')
+                  cp -R $(realpath -m $attached_vol_mnt_pt)/ .
+                  echo "attached vol $(realpath -m $attached_vol)"
+                  eval "hdiutil detach \"$attached_vol\""
+                }}
+
+                _extract_dmg
+
+                "#,
+                    asset_path.display()
+                ),
+                current_dir,
+            )?;
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod extract_asset_tests {
+    use super::*;
+    use std::io::Write;
+
+    fn get_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "orbiter_extract_asset_tests_{}_{}",
+            name,
+            std::process::id()
+        ));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(dir.join("out")).unwrap();
+
+        dir
+    }
+
+    fn get_header(path: &[u8], entry_type: tar::EntryType, size: u64) -> tar::Header {
+        // set through the raw name, as tar::Header::set_path refuses `..`
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path);
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(0o755);
+        header
+    }
+
+    fn write_tar_gz(path: &Path, entries: &[(&str, tar::EntryType, &str)]) {
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for (name, entry_type, content) in entries {
+            let mut header = get_header(name.as_bytes(), *entry_type, 0);
+            if entry_type.is_symlink() || entry_type.is_hard_link() {
+                header.set_link_name(content).unwrap();
+                header.set_cksum();
+                builder.append(&header, io::empty()).unwrap();
+            } else {
+                header.set_size(content.len() as u64);
+                header.set_cksum();
+                builder.append(&header, content.as_bytes()).unwrap();
             }
-            _ => {}
         }
+        builder.into_inner().unwrap().finish().unwrap();
     }
 
-    Ok(())
+    fn extract(asset_path: &Path, strip_components: usize) -> Result<(), String> {
+        let out = asset_path.parent().unwrap().join("out");
+        extract_asset(&SupportedShell::Sh, asset_path, &out, strip_components)
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn it_should_detect_formats_by_name() {
+        assert_eq!(
+            get_archive_format_by_name("fd-v10.1.0-x86_64-unknown-linux-musl.TAR.GZ"),
+            Some(ArchiveFormat::Tar(Compression::Gzip))
+        );
+        assert_eq!(
+            get_archive_format_by_name("nvim-linux64.tar.zst"),
+            Some(ArchiveFormat::Tar(Compression::Zstd))
+        );
+        assert_eq!(
+            get_archive_format_by_name("rust-analyzer-x86_64-unknown-linux-gnu.gz"),
+            Some(ArchiveFormat::Compressed(Compression::Gzip))
+        );
+        assert_eq!(get_archive_format_by_name("kind-linux-amd64"), None);
+    }
+
+    #[test]
+    fn it_should_extract_tarballs_with_stripped_components() {
+        let dir = get_test_dir("strip");
+        let asset_path = dir.join("fd.tar.gz");
+        write_tar_gz(
+            &asset_path,
+            &[
+                ("fd-v10/", tar::EntryType::Directory, ""),
+                ("fd-v10/bin/fd", tar::EntryType::Regular, "fd"),
+                ("fd-v10/fd-link", tar::EntryType::Symlink, "bin/fd"),
+            ],
+        );
+
+        assert_eq!(extract(&asset_path, 1), Ok(()));
+        assert_eq!(fs::read_to_string(dir.join("out/bin/fd")).unwrap(), "fd");
+        assert_eq!(fs::read_to_string(dir.join("out/fd-link")).unwrap(), "fd");
+    }
+
+    #[test]
+    fn it_should_refuse_entries_escaping_the_install_dir() {
+        let dir = get_test_dir("escape");
+        let traversal_path = dir.join("traversal.tar.gz");
+        write_tar_gz(
+            &traversal_path,
+            &[("../evil", tar::EntryType::Regular, "evil")],
+        );
+        let symlink_path = dir.join("symlink.tar.gz");
+        write_tar_gz(
+            &symlink_path,
+            &[
+                ("lib", tar::EntryType::Symlink, "../.."),
+                ("lib/evil", tar::EntryType::Regular, "evil"),
+            ],
+        );
+        let nested_symlink_path = dir.join("nested.tar.gz");
+        write_tar_gz(
+            &nested_symlink_path,
+            &[
+                ("lib", tar::EntryType::Symlink, "."),
+                ("lib/evil", tar::EntryType::Regular, "evil"),
+            ],
+        );
+
+        assert_eq!(
+            extract(&traversal_path, 0),
+            Err("archive entry ../evil points outside of the install dir".to_string())
+        );
+        assert_eq!(
+            extract(&symlink_path, 0),
+            Err("archive symlink lib -> ../.. points outside of the install dir".to_string())
+        );
+        let chain_path = dir.join("chain.tar.gz");
+        write_tar_gz(
+            &chain_path,
+            &[
+                ("x", tar::EntryType::Symlink, "."),
+                ("y", tar::EntryType::Symlink, "x/x/x/../../.."),
+            ],
+        );
+        let late_chain_path = dir.join("late_chain.tar.gz");
+        write_tar_gz(
+            &late_chain_path,
+            &[
+                ("y", tar::EntryType::Symlink, "x/x/x/../../.."),
+                ("x", tar::EntryType::Symlink, "."),
+            ],
+        );
+        let hard_link_path = dir.join("hard_link.tar.gz");
+        write_tar_gz(
+            &hard_link_path,
+            &[
+                ("x", tar::EntryType::Symlink, "."),
+                ("passwd", tar::EntryType::Link, "x/etc/passwd"),
+            ],
+        );
+
+        let zip_chain_path = dir.join("chain.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_chain_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_symlink("x", ".", options).unwrap();
+        writer.add_symlink("y", "x/x/x/../../..", options).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            extract(&nested_symlink_path, 0),
+            Err("archive entry lib/evil is placed through a symlink".to_string())
+        );
+        assert_eq!(
+            extract(&chain_path, 0),
+            Err("archive symlink y -> x/x/x/../../.. passes through a symlink".to_string())
+        );
+        fs::remove_dir_all(dir.join("out")).unwrap();
+        assert_eq!(
+            extract(&late_chain_path, 0),
+            Err(
+                "archive symlink y -> x/x/x/../../.. points outside of the install dir".to_string()
+            )
+        );
+        fs::remove_dir_all(dir.join("out")).unwrap();
+        assert_eq!(
+            extract(&hard_link_path, 0),
+            Err("archive hard link passwd -> x/etc/passwd passes through a symlink".to_string())
+        );
+        fs::remove_dir_all(dir.join("out")).unwrap();
+        assert_eq!(
+            extract(&zip_chain_path, 0),
+            Err("archive symlink y -> x/x/x/../../.. passes through a symlink".to_string())
+        );
+        assert!(!dir.join("evil").exists());
+    }
+
+    #[test]
+    fn it_should_extract_zips_and_compressed_binaries() {
+        let dir = get_test_dir("zip");
+        let zip_path = dir.join("tool.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o755);
+        writer.start_file("tool-1.0/tool", options).unwrap();
+        writer.write_all(b"tool").unwrap();
+        writer.finish().unwrap();
+
+        let gz_path = dir.join("rust-analyzer.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&gz_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(b"rust-analyzer").unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(extract(&zip_path, 1), Ok(()));
+        assert_eq!(fs::read_to_string(dir.join("out/tool")).unwrap(), "tool");
+        assert_eq!(extract(&gz_path, 0), Ok(()));
+        assert_eq!(
+            fs::read_to_string(dir.join("out/rust-analyzer")).unwrap(),
+            "rust-analyzer"
+        );
+    }
+
+    fn get_tar_gz(entries: &[(&str, &str)]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in entries {
+            let mut header = get_header(name.as_bytes(), tar::EntryType::Regular, 0);
+            header.set_size(content.len() as u64);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn get_cpio(entries: &[(&str, u32, &str)]) -> Vec<u8> {
+        let mut cpio = vec![];
+        for (index, (name, mode, content)) in entries
+            .iter()
+            .chain([("TRAILER!!!", 0, "")].iter())
+            .enumerate()
+        {
+            let name_size = name.len() + 1;
+            cpio.extend(
+                format!(
+                    "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+                    index, mode, 0, 0, 1, 0, content.len(), 0, 0, 0, 0, name_size, 0
+                )
+                .as_bytes(),
+            );
+            cpio.extend(name.as_bytes());
+            cpio.push(0);
+            cpio.resize(cpio.len() + (4 - (110 + name_size) % 4) % 4, 0);
+            cpio.extend(content.as_bytes());
+            cpio.resize(cpio.len() + (4 - content.len() % 4) % 4, 0);
+        }
+        cpio
+    }
+
+    #[test]
+    fn it_should_extract_only_the_data_of_debs() {
+        let dir = get_test_dir("deb");
+        let deb_path = dir.join("tool_1.0_amd64.deb");
+        let mut builder = ar::Builder::new(File::create(&deb_path).unwrap());
+        for (name, content) in [
+            ("debian-binary", b"2.0\n".to_vec()),
+            (
+                "control.tar.gz",
+                get_tar_gz(&[("./control", "Package: tool")]),
+            ),
+            ("data.tar.gz", get_tar_gz(&[("./usr/bin/tool", "tool")])),
+        ] {
+            let header = ar::Header::new(name.as_bytes().to_vec(), content.len() as u64);
+            builder.append(&header, content.as_slice()).unwrap();
+        }
+        drop(builder);
+
+        assert_eq!(extract(&deb_path, 1), Ok(()));
+        assert_eq!(
+            fs::read_to_string(dir.join("out/bin/tool")).unwrap(),
+            "tool"
+        );
+        assert!(!dir.join("out/control").exists());
+        assert!(!dir.join("out/debian-binary").exists());
+    }
+
+    #[test]
+    fn it_should_extract_rpm_payloads() {
+        let dir = get_test_dir("rpm");
+        let rpm_path = dir.join("tool-1.0-1.x86_64.rpm");
+        let mut rpm = vec![0xed, 0xab, 0xee, 0xdb];
+        rpm.resize(96, 0);
+        // signature of one index entry and 4 bytes of data, padded to 8 bytes
+        rpm.extend([0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4]);
+        rpm.extend([0u8; 16 + 4 + 4]);
+        rpm.extend([0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        rpm.extend([0u8; 3]);
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder
+            .write_all(&get_cpio(&[
+                ("./usr/bin", 0o040755, ""),
+                ("./usr/bin/tool", 0o100755, "tool"),
+                ("./usr/bin/tool-link", 0o120777, "tool"),
+            ]))
+            .unwrap();
+        rpm.extend(encoder.finish().unwrap());
+        fs::write(&rpm_path, rpm).unwrap();
+
+        assert_eq!(extract(&rpm_path, 2), Ok(()));
+        assert_eq!(fs::read_to_string(dir.join("out/tool")).unwrap(), "tool");
+        assert_eq!(
+            fs::read_to_string(dir.join("out/tool-link")).unwrap(),
+            "tool"
+        );
+    }
+
+    #[test]
+    fn it_should_name_appimages_after_their_desktop_entry() {
+        assert_eq!(
+            get_desktop_exec_name("[Desktop Entry]\nName=Neovim\nExec=nvim %F\n").as_deref(),
+            Some("nvim")
+        );
+        assert_eq!(
+            get_desktop_exec_name("[Desktop Entry]\nExec=\"/usr/bin/obsidian\"\n").as_deref(),
+            Some("obsidian")
+        );
+        assert_eq!(get_desktop_exec_name("[Desktop Entry]\n"), None);
+    }
 }
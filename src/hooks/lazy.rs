@@ -0,0 +1,166 @@
+use crate::utils::{config::Trigger, shells::SupportedShell};
+
+// shell function name safe for any payload id
+fn get_loader_name(payload_id: &str) -> String {
+    let id = payload_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    format!("__orbiter_load_{}", id)
+}
+
+fn get_trigger_cmds(trigger: &Option<Trigger>) -> Vec<String> {
+    match trigger {
+        Some(Trigger::Single(cmd)) => vec![cmd.to_owned()],
+        Some(Trigger::Multiple(cmds)) => cmds.to_owned(),
+        None => vec![],
+    }
+}
+
+// wrap the src and load code of a payload to run after the first prompt,
+// or on the first call of a trigger command; shells without either load eagerly
+pub fn lazy(
+    current_shell: &SupportedShell,
+    payload_id: &str,
+    rendered: &str,
+    is_deferred: bool,
+    trigger: &Option<Trigger>,
+) -> String {
+    let trigger_cmds = get_trigger_cmds(trigger);
+    if rendered.is_empty() || (!is_deferred && trigger_cmds.is_empty()) {
+        return rendered.to_owned();
+    }
+
+    match current_shell {
+        SupportedShell::Sh | SupportedShell::Zsh | SupportedShell::Bash => lazy_sh(
+            current_shell,
+            payload_id,
+            rendered,
+            is_deferred,
+            &trigger_cmds,
+        ),
+        SupportedShell::Fish => lazy_fish(payload_id, rendered, is_deferred, &trigger_cmds),
+        _ => rendered.to_owned(),
+    }
+}
+
+fn lazy_sh(
+    current_shell: &SupportedShell,
+    payload_id: &str,
+    rendered: &str,
+    is_deferred: bool,
+    trigger_cmds: &[String],
+) -> String {
+    let loader = get_loader_name(payload_id);
+    // the loader turns itself into a no-op so it only ever runs once
+    let mut wrapped = format!("{loader}() {{\n{loader}() {{ :; }}\n{rendered}}}\n");
+
+    if !trigger_cmds.is_empty() {
+        let cmds = trigger_cmds.join(" ");
+        for cmd in trigger_cmds {
+            wrapped.push_str(&format!(
+                "{cmd}() {{ unset -f {cmds}; {loader}; {cmd} \"$@\"; }}\n"
+            ));
+        }
+    }
+
+    if is_deferred {
+        match current_shell {
+            SupportedShell::Zsh => {
+                let hook = format!("{loader}_precmd");
+                wrapped.push_str(&format!(
+                    "{hook}() {{ add-zsh-hook -d precmd {hook}; unset -f {hook}; {loader}; }}\n\
+                     autoload -Uz add-zsh-hook && add-zsh-hook precmd {hook}\n"
+                ));
+            }
+            SupportedShell::Bash => {
+                let hook = format!("{loader}_prompt");
+                wrapped.push_str(&format!(
+                    "{hook}() {{ PROMPT_COMMAND=\"${{PROMPT_COMMAND//{hook};/}}\"; unset -f {hook}; {loader}; }}\n\
+                     PROMPT_COMMAND=\"{hook};${{PROMPT_COMMAND}}\"\n"
+                ));
+            }
+            // no prompt hooks to defer to
+            _ => wrapped.push_str(&format!("{loader}\n")),
+        }
+    }
+
+    wrapped
+}
+
+fn lazy_fish(
+    payload_id: &str,
+    rendered: &str,
+    is_deferred: bool,
+    trigger_cmds: &[String],
+) -> String {
+    let loader = get_loader_name(payload_id);
+    let mut wrapped = format!("function {loader}\nfunction {loader}; end\n{rendered}end\n");
+
+    if !trigger_cmds.is_empty() {
+        let cmds = trigger_cmds.join(" ");
+        for cmd in trigger_cmds {
+            wrapped.push_str(&format!(
+                "function {cmd}; functions -e {cmds}; {loader}; {cmd} $argv; end\n"
+            ));
+        }
+    }
+
+    if is_deferred {
+        wrapped.push_str(&format!(
+            "function {loader}_prompt --on-event fish_prompt; functions -e {loader}_prompt; {loader}; end\n"
+        ));
+    }
+
+    wrapped
+}
+
+#[cfg(test)]
+mod lazy_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_load_eagerly_by_default() {
+        let rendered = ". /tmp/plugin.zsh\n";
+
+        assert_eq!(
+            lazy(&SupportedShell::Zsh, "plugin", rendered, false, &None),
+            rendered
+        );
+    }
+
+    #[test]
+    fn it_should_load_on_trigger() {
+        let actual = lazy(
+            &SupportedShell::Zsh,
+            "nvm-sh",
+            "echo load\n",
+            false,
+            &Some(Trigger::Multiple(vec![
+                "nvm".to_string(),
+                "node".to_string(),
+            ])),
+        );
+
+        assert_eq!(
+            actual,
+            "__orbiter_load_nvm_sh() {\n__orbiter_load_nvm_sh() { :; }\necho load\n}\n\
+             nvm() { unset -f nvm node; __orbiter_load_nvm_sh; nvm \"$@\"; }\n\
+             node() { unset -f nvm node; __orbiter_load_nvm_sh; node \"$@\"; }\n"
+        );
+    }
+
+    #[test]
+    fn it_should_defer_to_the_first_prompt() {
+        let actual = lazy(&SupportedShell::Zsh, "plugin", "echo load\n", true, &None);
+
+        assert_eq!(
+            actual,
+            "__orbiter_load_plugin() {\n__orbiter_load_plugin() { :; }\necho load\n}\n\
+             __orbiter_load_plugin_precmd() { add-zsh-hook -d precmd __orbiter_load_plugin_precmd; \
+             unset -f __orbiter_load_plugin_precmd; __orbiter_load_plugin; }\n\
+             autoload -Uz add-zsh-hook && add-zsh-hook precmd __orbiter_load_plugin_precmd\n"
+        );
+    }
+}
@@ -5,6 +5,6 @@ use crate::utils::{
 pub fn load(
     current_shell: &SupportedShell,
     load_evaluatable: &ShellSpecificEvaluatable,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<String, Box<dyn std::error::Error>> {
     process_evaluatable(current_shell, load_evaluatable)
 }
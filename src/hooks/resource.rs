@@ -1,22 +1,39 @@
-use crate::providers::github::get_repo_release_asset_url;
+use crate::providers::release::get_provider_request;
+use crate::providers::release::get_repo_release_asset;
+use crate::providers::version::{filter_tags, find_matching_tag};
 use crate::providers::Providers;
+use crate::utils::checksum::*;
 use crate::utils::config::*;
+use crate::utils::download_cache::*;
+use crate::utils::httpclient::error_for_status;
 use crate::utils::httpclient::get_resource_name;
 use crate::utils::httpclient::get_resource_name_from_url;
+use crate::utils::httpclient::get_text;
+use crate::utils::httpclient::send;
+use crate::utils::lockfile::LockedPayload;
 use crate::utils::paths::*;
+use crate::utils::platform::{get_arch, get_os};
 use crate::utils::script::*;
+use crate::utils::signature::*;
 
 use log::error;
-use reqwest::Url;
-use reqwest::{self};
-use std::env;
+use log::info;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::{HeaderName, ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{self, StatusCode};
 use std::ffi::OsStr;
 use std::fs;
-use std::fs::{rename, File};
-use std::io;
+use std::fs::rename;
 use std::path::Path;
 use std::path::PathBuf;
 
+// resource fetched for a payload, along with the lock entry that pins it
+#[derive(Debug, Default)]
+pub struct FetchedResource {
+    pub asset_path: Option<PathBuf>,
+    pub locked: LockedPayload,
+}
+
 fn move_resource_to_current_dir(
     resource_path: &Path,
     payload_current_install_dir: &Path,
@@ -26,12 +43,12 @@ fn move_resource_to_current_dir(
         .and_then(OsStr::to_str)
         .unwrap();
 
-    fs::create_dir_all(&payload_current_install_dir)?;
+    fs::create_dir_all(payload_current_install_dir)?;
 
-    let dest = payload_current_install_dir.join(&resource_name);
+    let dest = payload_current_install_dir.join(resource_name);
 
     // move file/dir to dest
-    rename(&resource_path, &dest)?;
+    rename(resource_path, &dest)?;
 
     Ok(dest)
 }
@@ -43,7 +60,7 @@ fn set_resource_as_current(
     let dest = payload_current_install_dir;
 
     // move file/dir to dest
-    rename(&resource_path, &dest)?;
+    rename(resource_path, dest)?;
 
     Ok(())
 }
@@ -52,78 +69,315 @@ fn clone_repo(
     payload_config_dir: &Path,
     repo: &Repo,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let url = match Providers::from(&repo.provider) {
-        Providers::GitHub => Url::parse(&format!("https://github.com/{}", &repo.repo))?,
-        Providers::GitLab => Url::parse(&format!("https://gitlab.com/{}", &repo.repo))?,
-        Providers::Gitee => Url::parse(&format!("https://gitee.com/{}", &repo.repo))?,
-    };
-
-    assert!(std::env::set_current_dir(&payload_config_dir).is_ok());
+    let url = Providers::from(&repo.provider).get_repo_url(repo)?;
 
-    git_cmd(&["clone", url.as_ref()])?;
+    git_cmd(&[
+        "-C",
+        &payload_config_dir.display().to_string(),
+        "clone",
+        url.as_ref(),
+    ])?;
 
     get_resource_name_from_url(&url)
 }
 
+// request for an asset, authenticated with the repo token for release assets
+fn get_asset_request(
+    url: &str,
+    repo: Option<&Repo>,
+) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+    match repo {
+        Some(repo) => {
+            Ok(get_provider_request(repo, url)?.header(ACCEPT, "application/octet-stream"))
+        }
+        None => Ok(reqwest::blocking::Client::builder()
+            .timeout(None)
+            .build()?
+            .get(url)),
+    }
+}
+
+fn get_asset_text(url: &str, repo: Option<&Repo>) -> Result<String, Box<dyn std::error::Error>> {
+    match repo {
+        Some(_) => Ok(error_for_status(send(get_asset_request(url, repo)?)?)?.text()?),
+        None => get_text(url),
+    }
+}
+
+// header value as a string, if present and valid
+fn get_header_str(res: &reqwest::blocking::Response, name: HeaderName) -> Option<String> {
+    res.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
 fn get_asset(
     payload_config_dir: &Path,
     current_install_dir: &Path,
     url: &str,
+    repo: Option<&Repo>,
+    expected_sha256: Option<&str>,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
-    let res = client.get(url).send()?;
-    let mut dest = {
-        let file_name = get_resource_name(&res)?;
-        let file_path = payload_config_dir.join(&file_name);
-        (File::create(&file_path)?, file_path)
+    let mut entry = match get_cache_entry(url)? {
+        // content already known to match, no need to ask the server
+        Some(entry)
+            if expected_sha256.is_some_and(|sha256| sha256.eq_ignore_ascii_case(&entry.sha256)) =>
+        {
+            info!("using cached {} ({})", url, &entry.sha256);
+            entry
+        }
+        cached => {
+            let mut req = get_asset_request(url, repo)?;
+            if let Some(entry) = &cached {
+                if let Some(etag) = &entry.etag {
+                    req = req.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &entry.last_modified {
+                    req = req.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let res = send(req)?;
+            match cached {
+                Some(entry) if res.status() == StatusCode::NOT_MODIFIED => {
+                    info!("using cached {} (not modified)", url);
+                    entry
+                }
+                _ => {
+                    let mut res = error_for_status(res)?;
+                    let file_name = get_resource_name(&res)?;
+                    let etag = get_header_str(&res, ETAG);
+                    let last_modified = get_header_str(&res, LAST_MODIFIED);
+                    add_cache_entry(url, &file_name, etag, last_modified, &mut res)?
+                }
+            }
+        }
     };
 
-    let file_content = res.bytes()?;
-    io::copy(&mut file_content.as_ref(), &mut dest.0)?;
+    let file_path = payload_config_dir.join(&entry.file_name);
+    copy_cache_entry(&mut entry, &file_path)?;
 
-    let dest_path = move_resource_to_current_dir(&dest.1, &current_install_dir)?;
+    let dest_path = move_resource_to_current_dir(&file_path, current_install_dir)?;
 
     Ok(dest_path)
 }
 
+// expected integrity of an asset, either configured or discovered alongside a release
+#[derive(Debug, Default)]
+struct AssetVerification<'a> {
+    repo: Option<&'a Repo>,
+    checksum: Option<&'a str>,
+    checksum_url: Option<String>,
+    signature: Option<&'a Signature>,
+}
+
+fn verify_asset(
+    asset_path: &Path,
+    url: &str,
+    verification: &AssetVerification,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // configured checksum takes precedence over a discovered one
+    if let Some(checksum) = verification.checksum {
+        verify_file_checksum(asset_path, &Checksum::parse(checksum)?)?;
+    } else if let Some(checksum_url) = &verification.checksum_url {
+        // named after the downloaded file, as API asset urls carry no file name
+        let asset_name = asset_path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default();
+        match find_checksum(
+            &get_asset_text(checksum_url, verification.repo)?,
+            asset_name,
+        ) {
+            Some(checksum) => verify_file_checksum(asset_path, &checksum)?,
+            None => {
+                return Err(format!("no checksum for {} in {}", &asset_name, checksum_url).into())
+            }
+        }
+    }
+
+    if let Some(signature) = verification.signature {
+        if let Some(public_key) = &signature.minisign {
+            let signature_url = signature
+                .url
+                .to_owned()
+                .unwrap_or(format!("{}.minisig", url));
+            verify_minisign(
+                asset_path,
+                public_key,
+                &get_asset_text(&signature_url, verification.repo)?,
+            )?;
+        } else if let Some(public_key) = &signature.cosign {
+            let signature_url = signature.url.to_owned().unwrap_or(format!("{}.sig", url));
+            verify_cosign(
+                asset_path,
+                public_key,
+                &get_asset_text(&signature_url, verification.repo)?,
+            )?;
+        } else {
+            return Err(format!("no minisign or cosign public key to verify {}", url).into());
+        }
+    }
+
+    Ok(())
+}
+
+fn get_locked_asset(
+    payload_config_dir: &Path,
+    current_install_dir: &Path,
+    url: &str,
+    tag: Option<String>,
+    pinned: Option<&LockedPayload>,
+    verification: &AssetVerification,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
+    // a locked or configured sha256 lets a cached download skip the network
+    let configured_sha256 = match verification.checksum.map(Checksum::parse) {
+        Some(Ok(Checksum::Sha256(digest))) => Some(digest),
+        _ => None,
+    };
+    let expected_sha256 = pinned
+        .and_then(|p| p.sha256.to_owned())
+        .or(configured_sha256);
+    let asset_path = get_asset(
+        payload_config_dir,
+        current_install_dir,
+        url,
+        verification.repo,
+        expected_sha256.as_deref(),
+    )?;
+    verify_asset(&asset_path, url, verification)?;
+
+    let sha256 = get_file_sha256(&asset_path)?;
+
+    // verify against the locked checksum
+    if let Some(locked_sha256) = pinned.and_then(|p| p.sha256.as_ref()) {
+        if !locked_sha256.eq_ignore_ascii_case(&sha256) {
+            return Err(format!(
+                "sha256 mismatch for {}: locked {}, downloaded {}",
+                url, locked_sha256, sha256
+            )
+            .into());
+        }
+    }
+
+    Ok(FetchedResource {
+        asset_path: Some(asset_path),
+        locked: LockedPayload {
+            tag,
+            url: Some(url.to_owned()),
+            commit: None,
+            sha256: Some(sha256),
+        },
+    })
+}
+
+fn get_head_commit(repo_dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let output = git_cmd(&["-C", &repo_dir.display().to_string(), "rev-parse", "HEAD"])?;
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+// tag matching ver, otherwise ver itself as a branch, tag or commit
+fn get_checkout_rev(
+    repo_dir: &Path,
+    repo: &Repo,
+    ver: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let output = git_cmd(&["-C", &repo_dir.display().to_string(), "tag", "--list"])?;
+    let tag_list = String::from_utf8(output.stdout)?;
+    let tags = filter_tags(&tag_list.lines().collect::<Vec<&str>>(), &repo.tag_pattern)?;
+
+    Ok(find_matching_tag(&tags, ver).unwrap_or(ver).to_string())
+}
+
 fn clone_and_checkout_repo(
     repo: &Repo,
     payload_config_dir: &Path,
     current_install_dir: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
+    pinned: Option<&LockedPayload>,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
     let repo_name = clone_repo(payload_config_dir, repo)?;
     let resource_path = payload_config_dir.join(&repo_name);
-    set_resource_as_current(&resource_path, &current_install_dir)?;
+    set_resource_as_current(&resource_path, current_install_dir)?;
+
+    // checkout locked commit, otherwise branch/tag
+    let locked_commit = pinned.and_then(|p| p.commit.as_ref());
+    let rev = match (locked_commit, &repo.ver) {
+        (Some(locked_commit), _) => Some(locked_commit.to_owned()),
+        (None, Some(ver)) => Some(get_checkout_rev(current_install_dir, repo, ver)?),
+        (None, None) => None,
+    };
+    if let Some(rev) = &rev {
+        git_cmd(&[
+            "-C",
+            &current_install_dir.display().to_string(),
+            "checkout",
+            "-q",
+            rev,
+        ])?;
+    };
 
-    // checkout branch/tag
-    if let Some(ver) = &repo.ver {
-        assert!(std::env::set_current_dir(&current_install_dir).is_ok());
-        git_cmd(&["checkout", "-q", &ver])?;
+    // resolved tag, or the one locked alongside the commit
+    let tag = match locked_commit {
+        Some(_) => pinned.and_then(|p| p.tag.to_owned()),
+        None => rev,
     };
 
-    Ok(())
+    let commit = get_head_commit(current_install_dir)?;
+    if let Some(locked_commit) = locked_commit {
+        if !commit.starts_with(locked_commit.as_str()) {
+            return Err(format!(
+                "unable to checkout locked commit {} of {}",
+                locked_commit, &repo.repo
+            )
+            .into());
+        }
+    }
+
+    Ok(FetchedResource {
+        asset_path: None,
+        locked: LockedPayload {
+            tag,
+            url: None,
+            commit: Some(commit),
+            sha256: None,
+        },
+    })
 }
 
 fn get_resource_repo(
     payload_config_dir: &Path,
     current_install_dir: &Path,
     repo: &Repo,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
-    let asset_path = if let Some(from_release) = repo.from_release {
-        if from_release {
-            // repo release
-            let url = get_repo_release_asset_url(&repo)?;
-            Some(get_asset(&payload_config_dir, &current_install_dir, &url)?)
-        } else {
-            clone_and_checkout_repo(repo, payload_config_dir, current_install_dir)?;
-            None
-        }
+    pinned: Option<&LockedPayload>,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
+    if repo.from_release.unwrap_or(false) {
+        // repo release, reuse the locked asset if any
+        let (tag, url, checksum_url) = match pinned.and_then(|p| p.url.as_ref()) {
+            Some(url) => (pinned.and_then(|p| p.tag.to_owned()), url.to_owned(), None),
+            None => {
+                let asset = get_repo_release_asset(repo)?;
+                (Some(asset.tag_name), asset.url, asset.checksum_url)
+            }
+        };
+
+        get_locked_asset(
+            payload_config_dir,
+            current_install_dir,
+            &url,
+            tag,
+            pinned,
+            &AssetVerification {
+                repo: Some(repo),
+                checksum: repo.checksum.as_deref(),
+                checksum_url,
+                signature: repo.signature.as_ref(),
+            },
+        )
     } else {
-        clone_and_checkout_repo(repo, payload_config_dir, current_install_dir)?;
-        None
-    };
-
-    Ok(asset_path)
+        clone_and_checkout_repo(repo, payload_config_dir, current_install_dir, pinned)
+    }
 }
 
 fn get_resource_location(
@@ -131,19 +385,25 @@ fn get_resource_location(
     current_install_dir: &Path,
     url: &str,
     init_result: Option<&str>,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
-    let asset_path = if let Some(init) = init_result {
-        let location = url.replace("{init}", init);
-        Some(get_asset(
-            &payload_config_dir,
-            &current_install_dir,
-            &location,
-        )?)
+    pinned: Option<&LockedPayload>,
+    verification: &AssetVerification,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
+    let location = if let Some(url) = pinned.and_then(|p| p.url.as_ref()) {
+        url.to_owned()
+    } else if let Some(init) = init_result {
+        url.replace("{init}", init)
     } else {
-        Some(get_asset(&payload_config_dir, &current_install_dir, &url)?)
+        url.to_owned()
     };
 
-    Ok(asset_path)
+    get_locked_asset(
+        payload_config_dir,
+        current_install_dir,
+        &location,
+        None,
+        pinned,
+        verification,
+    )
 }
 
 pub fn get_resource(
@@ -151,26 +411,50 @@ pub fn get_resource(
     current_install_dir: &Path,
     resource: &Resource,
     init_result: Option<&str>,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    pinned: Option<&LockedPayload>,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
     match &resource {
-        Resource::Repo(repo) => get_resource_repo(payload_config_dir, current_install_dir, repo),
-        Resource::Location(url) => {
-            get_resource_location(payload_config_dir, current_install_dir, url, init_result)
+        Resource::Repo(repo) => {
+            get_resource_repo(payload_config_dir, current_install_dir, repo, pinned)
         }
+        Resource::Location(url) => get_resource_location(
+            payload_config_dir,
+            current_install_dir,
+            url,
+            init_result,
+            pinned,
+            &AssetVerification::default(),
+        ),
+        Resource::VerifiedLocation(location) => get_resource_location(
+            payload_config_dir,
+            current_install_dir,
+            &location.url,
+            init_result,
+            pinned,
+            &AssetVerification {
+                repo: None,
+                checksum: location.checksum.as_deref(),
+                checksum_url: None,
+                signature: location.signature.as_ref(),
+            },
+        ),
     }
 }
 
-fn get_os_specific_resource(
-    payload_config_dir: &Path,
-    current_install_dir: &Path,
-    init_result: Option<&str>,
-    resource: &SupportedOSSpecificResource,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
-    let os = std::env::consts::OS;
+// resource for the current os and arch, if the payload has one
+pub fn get_platform_resource(resource: &AdaptiveResource) -> Option<&Resource> {
+    let os_specific_resource = match resource {
+        AdaptiveResource::Standard(resource) => return Some(resource),
+        AdaptiveResource::OSSpecific(os_specific_resource) => os_specific_resource,
+    };
+
+    let os = get_os();
     let supported_os_specific_resource = match os {
-        "linux" => &resource.linux,
-        "macos" => &resource.macos,
-        "windows" => &resource.windows,
+        "linux" => &os_specific_resource.linux,
+        "macos" => &os_specific_resource.macos,
+        "windows" => &os_specific_resource.windows,
+        "freebsd" => &os_specific_resource.freebsd,
+        "netbsd" => &os_specific_resource.netbsd,
         _ => {
             error!("unsupported os: {}", os);
 
@@ -178,73 +462,45 @@ fn get_os_specific_resource(
         }
     };
 
-    let resource_path = if let Some(os_specific_resource) = supported_os_specific_resource {
-        match os_specific_resource {
-            OSSpecificResource::Standard(res) => {
-                get_resource(payload_config_dir, current_install_dir, res, init_result)?
-            }
-            OSSpecificResource::ArchSpecific(res) => get_arch_specific_resource(
-                &payload_config_dir,
-                &current_install_dir,
-                init_result,
-                res,
-            )?,
+    match supported_os_specific_resource.as_ref()? {
+        OSSpecificResource::Standard(resource) => Some(resource),
+        OSSpecificResource::ArchSpecific(arch_specific_resource) => {
+            let machine_arch = get_arch();
+            let supported_arch_specific_resource = match machine_arch {
+                "x86_64" => &arch_specific_resource.x86_64,
+                "aarch64" => &arch_specific_resource.aarch64,
+                "i686" => &arch_specific_resource.i686,
+                "armv7" => &arch_specific_resource.armv7,
+                "riscv64" => &arch_specific_resource.riscv64,
+                "ppc64le" => &arch_specific_resource.ppc64le,
+                "s390x" => &arch_specific_resource.s390x,
+                _ => {
+                    error!("unsupported arch: {}", machine_arch);
+                    &None
+                }
+            };
+
+            supported_arch_specific_resource.as_ref()
         }
-    } else {
-        None
-    };
-
-    Ok(resource_path)
-}
-
-fn get_arch_specific_resource(
-    payload_config_dir: &Path,
-    current_install_dir: &Path,
-    init_result: Option<&str>,
-    resource: &SupportedArchSpecificResource,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
-    let machine_arch = env::consts::ARCH;
-    let supported_arch_specific_resource = match machine_arch.as_ref() {
-        "x86_64" | "amd64" => &resource.x86_64,
-        "aarch64" | "arm64" => &resource.aarch64,
-        _ => {
-            error!("Unsupported architecture: {}", machine_arch);
-            &None
-        }
-    };
-
-    let resource_path = if let Some(res) = supported_arch_specific_resource {
-        get_resource(payload_config_dir, current_install_dir, res, init_result)?
-    } else {
-        None
-    };
-
-    Ok(resource_path)
+    }
 }
 
 pub fn get_adaptive_resource(
     payload: &Payload,
     init_result: Option<&str>,
-) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
-    let payload_config_dir = get_payload_config_dir_path(&payload)?;
-    let current_install_dir = get_payload_current_install_dir_path(&payload)?;
-
-    let asset_path = match &payload.resource {
-        AdaptiveResource::Standard(resource) => match resource {
-            Resource::Repo(repo) => {
-                get_resource_repo(&payload_config_dir, &current_install_dir, repo)?
-            }
-            Resource::Location(url) => {
-                get_resource_location(&payload_config_dir, &current_install_dir, url, init_result)?
-            }
-        },
-        AdaptiveResource::OSSpecific(os_specific_resource) => get_os_specific_resource(
+    pinned: Option<&LockedPayload>,
+) -> Result<FetchedResource, Box<dyn std::error::Error>> {
+    let payload_config_dir = get_payload_config_dir_path(payload)?;
+    let current_install_dir = get_payload_current_install_dir_path(payload)?;
+
+    match get_platform_resource(&payload.resource) {
+        Some(resource) => get_resource(
             &payload_config_dir,
             &current_install_dir,
+            resource,
             init_result,
-            os_specific_resource,
-        )?,
-    };
-
-    Ok(asset_path)
+            pinned,
+        ),
+        None => Ok(FetchedResource::default()),
+    }
 }
@@ -1,6 +1,7 @@
 use glob::glob;
 
 use std::fs;
+use std::path::Path;
 
 use crate::utils::{
     config::{ShellSpecificSourceTarget, SourceTarget},
@@ -10,9 +11,10 @@ use crate::utils::{
 pub fn src(
     current_shell: &SupportedShell,
     specified_src_target: &ShellSpecificSourceTarget,
-) -> Result<(), Box<dyn std::error::Error>> {
+    current_dir: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
     match specified_src_target {
-        ShellSpecificSourceTarget::Generic(generic) => process_src_target(&generic),
+        ShellSpecificSourceTarget::Generic(generic) => process_src_target(generic, current_dir),
         ShellSpecificSourceTarget::ShellSpecific(shell_specific) => {
             let op_shell_specific_target = match current_shell {
                 SupportedShell::Sh => &shell_specific.sh,
@@ -24,43 +26,57 @@ pub fn src(
             };
 
             if let Some(shell_specific_target) = op_shell_specific_target {
-                process_src_target(&shell_specific_target)
+                process_src_target(shell_specific_target, current_dir)
             } else {
-                Ok(())
+                Ok(String::new())
             }
         }
     }
 }
 
-pub fn src_files(files: &Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+pub fn src_files(
+    files: &[String],
+    current_dir: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut sourced = String::new();
     for f in files {
         if f.contains("*") {
-            // handle globs
-            for entry in glob(&f).expect(&format!("unable to locate {}", f)) {
-                if let Ok(entry_path) = entry {
-                    print_src_path_canonical(&entry_path.display().to_string());
-                }
+            // handle globs, relative to the payload dir
+            let pattern = Path::new(&glob::Pattern::escape(&current_dir.display().to_string()))
+                .join(f)
+                .display()
+                .to_string();
+            for entry_path in glob(&pattern)
+                .unwrap_or_else(|_| panic!("unable to locate {}", f))
+                .flatten()
+            {
+                sourced.push_str(&get_src_path_canonical(&entry_path));
             }
         } else {
-            print_src_path_canonical(&f);
+            sourced.push_str(&get_src_path_canonical(&current_dir.join(f)));
         }
     }
 
-    Ok(())
+    Ok(sourced)
 }
 
-fn print_src_path_canonical(path: &str) {
+fn get_src_path_canonical(path: &Path) -> String {
     if let Ok(canonical_path) = fs::canonicalize(path) {
-        println!(". {}", &canonical_path.display().to_string());
-    };
+        format!(". {}\n", &canonical_path.display().to_string())
+    } else {
+        String::new()
+    }
 }
 
-fn process_src_target(target: &SourceTarget) -> Result<(), Box<dyn std::error::Error>> {
+fn process_src_target(
+    target: &SourceTarget,
+    current_dir: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
     match target {
         SourceTarget::Single(target) => {
             let src_target = vec![target.to_owned()];
-            src_files(&src_target)
+            src_files(&src_target, current_dir)
         }
-        SourceTarget::Multiple(targets) => src_files(targets),
+        SourceTarget::Multiple(targets) => src_files(targets, current_dir),
     }
 }
@@ -1,82 +1,440 @@
 use clap::Parser;
-use log::error;
-use orbiter::utils::completion::load_completion;
+use log::{error, warn};
+use orbiter::utils::completion::get_completion;
 use orbiter::utils::config::Payload;
 use orbiter::utils::listing::get_listing;
 use orbiter::utils::listing::ListingScope;
-use orbiter::utils::paths::update_path;
+use orbiter::utils::paths::get_path_export;
 use orbiter::utils::shells::SupportedShell;
 
+use orbiter::utils::check;
 use orbiter::utils::cli;
+use orbiter::utils::condition;
 use orbiter::utils::config;
+use orbiter::utils::dependency;
+use orbiter::utils::download_cache;
+use orbiter::utils::init_cache;
+use orbiter::utils::lockfile;
 use orbiter::utils::pipeline;
+use orbiter::utils::resolve;
+use orbiter::utils::state;
+use orbiter::utils::uninstall;
 use orbiter::utils::update;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
     let cmd = cli::Cli::parse();
+    if cmd.verbose {
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("orbiter=info"))
+            .init();
+    } else {
+        env_logger::init();
+    }
 
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     let result = match &cmd.command {
-        cli::Commands::Init { shell } => {
+        cli::Commands::Init {
+            shell,
+            locked,
+            apply_drift,
+            jobs,
+            no_cache,
+        } => {
             let current_shell = SupportedShell::from_str(shell);
 
-            // update PATH env var to enable shims
-            update_path(&current_shell);
+            // fast path: nothing changed since the init script was last rendered
+            let fresh_cache = if *no_cache || *apply_drift {
+                None
+            } else {
+                init_cache::get_fresh_init_cache(&current_shell)?
+            };
+
+            match fresh_cache {
+                Some(cache_path) => {
+                    println!("{}", current_shell.get_source_cmd(&cache_path));
+                    Ok(())
+                }
+                None => init_shell(&current_shell, *locked, *apply_drift, get_jobs(jobs)),
+            }
+        }
+        cli::Commands::Install {
+            ids,
+            shell,
+            locked,
+            apply_drift,
+            jobs,
+        } => {
+            let current_shell = match shell {
+                Some(shell) => SupportedShell::from_str(shell),
+                None => SupportedShell::from_env(),
+            };
 
-            init_shell(&current_shell)
+            init_cache::clear_init_cache()?;
+            install_payloads(&current_shell, ids, *locked, *apply_drift, get_jobs(jobs))
+        }
+        cli::Commands::Uninstall { id } => {
+            println!("Uninstalling payload: {}", &id);
+            init_cache::clear_init_cache()?;
+            uninstall::uninstall_payload(id)
         }
         cli::Commands::Update { id } => {
             let payloads = config::get_payloads()?;
-            if let Some(payload_id) = id {
+            init_cache::clear_init_cache()?;
+            let is_updated = if let Some(payload_id) = id {
                 println!("Updating payload: {:?}", &payload_id);
-                update_payload(&payloads, payload_id)?
+                update_payload(&payloads, payload_id)
             } else {
-                update::self_update()?
+                update::self_update().map(|_| true)
             };
 
-            println!("Restart terminal to take effect");
+            is_updated.map(|is_updated| {
+                if is_updated {
+                    println!("Restart terminal to take effect");
+                }
+            })
+        }
+        cli::Commands::Resolve { id } => {
+            let payloads = config::get_payloads()?;
+            match payloads.iter().find(|payload| &payload.id == id) {
+                Some(payload) => {
+                    for line in resolve::get_resolution(payload)? {
+                        println!("{}", line);
+                    }
+                    Ok(())
+                }
+                None => Err(format!("Payload with id {} not found", id).into()),
+            }
+        }
+        cli::Commands::Check => {
+            let diagnostics = check::check_config()?;
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
 
+            if !diagnostics.is_empty() {
+                eprintln!("{} problem(s) found", diagnostics.len());
+                std::process::exit(1);
+            }
+
+            println!("Config is valid");
+            Ok(())
+        }
+        cli::Commands::Schema => {
+            println!("{}", config::get_config_schema()?);
             Ok(())
         }
         cli::Commands::List { scope } => {
             let payloads = config::get_payloads()?;
             // list items
             let listing_scope = ListingScope::from(scope);
-            let listing = get_listing(&payloads, &listing_scope)?;
+            let listing = get_listing(&payloads, &listing_scope, &SupportedShell::from_env())?;
             println!("{:?} payloads: {:?}", &listing_scope, &listing);
 
             Ok(())
         }
+        cli::Commands::Cache { command } => match command {
+            cli::CacheCommands::List => {
+                let entries = download_cache::list_cache_entries()?;
+                let mut blobs = std::collections::HashSet::new();
+                let mut total_size = 0;
+                for entry in &entries {
+                    println!(
+                        "{:>10}  {}  {}",
+                        download_cache::format_size(entry.size),
+                        &entry.sha256[..12],
+                        &entry.url
+                    );
+                    if blobs.insert(&entry.sha256) {
+                        total_size += entry.size;
+                    }
+                }
+
+                println!(
+                    "{} download(s), {} of {}",
+                    entries.len(),
+                    download_cache::format_size(total_size),
+                    download_cache::format_size(download_cache::get_cache_max_size()?)
+                );
+                Ok(())
+            }
+            cli::CacheCommands::Clean => {
+                download_cache::clean_cache()?;
+                println!("Download cache cleared");
+                Ok(())
+            }
+        },
     };
 
-    Ok(if let Err(e) = result {
+    if let Err(e) = result {
         error!("Orbiter has encountered an error: {e}");
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+// archive drifted payloads to have them reinstalled, or warn about them
+fn check_config_drift(
+    payload: &Payload,
+    is_apply_drift: bool,
+    lockfile: &mut lockfile::Lockfile,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if !state::is_config_drifted(payload)? {
+        return Ok(false);
+    }
+
+    if is_apply_drift {
+        update::update_payload(payload)?;
+        lockfile.payloads.remove(&payload.id);
+    } else {
+        eprintln!(
+            "config drift: payload {} changed since it was installed, run `orbiter install --apply-drift {}` to reinstall it",
+            &payload.id, &payload.id
+        );
+    }
+
+    Ok(is_apply_drift)
+}
+
+// payloads whose when conditions hold on this host
+fn get_enabled_payloads<'a>(
+    payloads: &'a [Payload],
+    current_shell: &SupportedShell,
+) -> Vec<&'a Payload> {
+    let skip_reasons = condition::get_skip_reasons(payloads, current_shell);
+
+    payloads
+        .iter()
+        .filter(|payload| !skip_reasons.contains_key(&payload.id))
+        .collect()
+}
+
+// once all installs are done, as workers evicting blobs could race with other workers
+// adding theirs
+fn evict_download_cache() {
+    // a full cache should not fail the install
+    if let Err(err) = download_cache::evict_cache() {
+        warn!("unable to evict download cache: {}", err);
+    }
+}
+
+fn get_jobs(jobs: &Option<usize>) -> usize {
+    jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
     })
 }
 
-fn init_shell(current_shell: &SupportedShell) -> Result<(), Box<dyn std::error::Error>> {
-    config::get_payloads()?.iter().for_each(|payload| {
-        pipeline::process_payload(current_shell, &payload)
-            .unwrap_or_else(|err| error!("error processing payload [{:#?}]: {}", &payload, &err))
-    });
+fn init_shell(
+    current_shell: &SupportedShell,
+    is_locked: bool,
+    is_apply_drift: bool,
+    jobs: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config::get_config()?;
+    let enabled_payloads = get_enabled_payloads(&config.payloads, current_shell);
+
+    // a broken depends_on only drops the payloads involved, not the whole init script
+    let unresolvable = dependency::get_unresolvable_payloads(&enabled_payloads);
+    let resolvable_payloads = enabled_payloads
+        .into_iter()
+        .filter(|payload| match unresolvable.get(&payload.id) {
+            Some(reason) => {
+                error!("error resolving payload {}: {}", &payload.id, reason);
+                false
+            }
+            None => true,
+        })
+        .collect::<Vec<&Payload>>();
+    let payloads = dependency::resolve_payloads(&resolvable_payloads, &[])?;
+    let mut lockfile = lockfile::get_lockfile()?;
+    let mut is_lockfile_changed = false;
+    // only cache a complete init script, so failed payloads are retried
+    let mut is_complete = unresolvable.is_empty();
+
+    for payload in &payloads {
+        match check_config_drift(payload, is_apply_drift, &mut lockfile) {
+            Ok(is_reinstalling) => is_lockfile_changed |= is_reinstalling,
+            Err(err) => error!("error reinstalling payload {}: {}", &payload.id, &err),
+        }
+    }
+
+    // lazy payloads not installed yet only get a shim installing them on first use
+    let (lazy_payloads, eager_payloads): (Vec<&Payload>, Vec<&Payload>) =
+        payloads.iter().partition(|payload| {
+            pipeline::is_lazy_payload(payload)
+                && !pipeline::is_payload_installed(payload).unwrap_or(false)
+        });
+
+    for payload in &lazy_payloads {
+        if let Err(err) = pipeline::create_lazy_shim(current_shell, payload) {
+            error!(
+                "error creating lazy shim of payload {}: {}",
+                &payload.id, &err
+            );
+            is_complete = false;
+        }
+    }
+
+    let results = pipeline::install_payloads_concurrently(
+        current_shell,
+        &eager_payloads,
+        &lockfile,
+        is_locked,
+        jobs,
+        &|_, _| {},
+    );
+    evict_download_cache();
+
+    // update PATH env var to enable shims
+    let mut script = get_path_export(current_shell)?;
+
+    // source and load in config order to keep the init script deterministic
+    for (payload, result) in eager_payloads.iter().zip(results) {
+        match result {
+            Ok(Some(locked)) => {
+                is_lockfile_changed |= lockfile.pin_payload(&payload.id, locked);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                error!("error processing payload [{:#?}]: {}", &payload, &err);
+                is_complete = false;
+                continue;
+            }
+        }
+
+        match pipeline::render_payload(current_shell, payload) {
+            Ok(rendered) => script.push_str(&rendered),
+            Err(err) => {
+                error!("error processing payload [{:#?}]: {}", &payload, &err);
+                is_complete = false;
+            }
+        }
+    }
+
+    // pin newly installed payloads
+    if is_lockfile_changed {
+        lockfile::save_lockfile(&lockfile)?;
+    }
 
     // enables completion for shells that require it
-    load_completion(current_shell);
+    script.push_str(&get_completion(current_shell));
+
+    if is_complete {
+        if let Err(err) = init_cache::save_init_cache(
+            current_shell,
+            &script,
+            &init_cache::InitCacheInputs::new(&config.files, &config.payloads),
+        ) {
+            error!("error caching init script: {}", &err);
+        }
+    } else {
+        init_cache::clear_init_cache()?;
+    }
+
+    print!("{}", script);
 
     Ok(())
 }
 
-fn update_payload(
-    payloads: &Vec<Payload>,
-    payload_id: &str,
+fn install_payloads(
+    current_shell: &SupportedShell,
+    ids: &[String],
+    is_locked: bool,
+    is_apply_drift: bool,
+    jobs: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(
-        if let Some(payload) = payloads.iter().find(|p| p.id == payload_id) {
-            update::update_payload(payload)?
-        } else {
-            error!("Payload with id {} not found", &payload_id)
+    let payloads = config::get_payloads()?;
+    let skip_reasons = condition::get_skip_reasons(&payloads, current_shell);
+    for id in ids {
+        if let Some(reason) = skip_reasons.get(id) {
+            return Err(format!("payload {} is skipped: {}", id, reason).into());
+        }
+    }
+
+    let selected_payloads =
+        dependency::resolve_payloads(&get_enabled_payloads(&payloads, current_shell), ids)?;
+
+    let mut lockfile = lockfile::get_lockfile()?;
+    let mut is_lockfile_changed = false;
+
+    for payload in &selected_payloads {
+        match check_config_drift(payload, is_apply_drift, &mut lockfile) {
+            Ok(true) => {
+                println!("Reinstalling drifted payload: {}", &payload.id);
+                is_lockfile_changed = true;
+            }
+            Ok(false) => {}
+            Err(err) => error!("error reinstalling payload {}: {}", &payload.id, &err),
+        }
+    }
+
+    println!(
+        "Installing {} payload(s) with {} worker(s)",
+        selected_payloads.len(),
+        jobs
+    );
+    let results = pipeline::install_payloads_concurrently(
+        current_shell,
+        &selected_payloads,
+        &lockfile,
+        is_locked,
+        jobs,
+        &|payload, result| match result {
+            Ok(Some(locked)) => {
+                let source = locked
+                    .tag
+                    .as_ref()
+                    .or(locked.commit.as_ref())
+                    .or(locked.url.as_ref())
+                    .map(|source| format!(" ({})", source))
+                    .unwrap_or_default();
+                println!("Installed {}{}", &payload.id, source);
+            }
+            Ok(None) => println!("{} is already installed", &payload.id),
+            Err(err) => error!("error installing payload {}: {}", &payload.id, &err),
         },
-    )
+    );
+    evict_download_cache();
+
+    let mut failed_ids = vec![];
+    for (payload, result) in selected_payloads.iter().zip(results) {
+        match result {
+            Ok(Some(locked)) => {
+                is_lockfile_changed |= lockfile.pin_payload(&payload.id, locked);
+            }
+            Ok(None) => {}
+            Err(_) => failed_ids.push(payload.id.to_owned()),
+        }
+    }
+
+    // pin newly installed payloads
+    if is_lockfile_changed {
+        lockfile::save_lockfile(&lockfile)?;
+    }
+
+    if !failed_ids.is_empty() {
+        return Err(format!("failed to install {}", failed_ids.join(", ")).into());
+    }
+
+    Ok(())
+}
+
+fn update_payload(
+    payloads: &[Payload],
+    payload_id: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if let Some(payload) = payloads.iter().find(|p| p.id == payload_id) {
+        if !update::is_update_available(payload)? {
+            println!("{} is up to date", &payload_id);
+            return Ok(false);
+        }
+
+        update::update_payload(payload)?
+    } else {
+        return Err(format!("Payload with id {} not found", &payload_id).into());
+    }
+
+    Ok(true)
 }
@@ -1,8 +1,26 @@
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::HeaderMap;
+use reqwest::Url;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::utils::config::Repo;
+
+use self::gitea::GiteaProvider;
+use self::gitee::GiteeProvider;
+use self::github::GitHubProvider;
+use self::gitlab::GitLabProvider;
+use self::release::Release;
+
+pub mod asset;
+pub mod gitea;
+pub mod gitee;
 pub mod github;
+pub mod gitlab;
+pub mod release;
+pub mod version;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Providers {
     #[serde(rename = "github")]
     GitHub,
@@ -10,6 +28,12 @@ pub enum Providers {
     GitLab,
     #[serde(rename = "gitee")]
     Gitee,
+    #[serde(rename = "gitea")]
+    Gitea,
+    #[serde(rename = "forgejo")]
+    Forgejo,
+    #[serde(rename = "codeberg")]
+    Codeberg,
 }
 
 impl From<&str> for Providers {
@@ -18,6 +42,9 @@ impl From<&str> for Providers {
             "github" => Providers::GitHub,
             "gitlab" => Providers::GitLab,
             "gitee" => Providers::Gitee,
+            "gitea" => Providers::Gitea,
+            "forgejo" => Providers::Forgejo,
+            "codeberg" => Providers::Codeberg,
             _ => Providers::GitHub,
         }
     }
@@ -35,7 +62,7 @@ impl From<&Option<String>> for Providers {
 
 impl From<&Providers> for Providers {
     fn from(s: &Providers) -> Self {
-        s.into()
+        s.clone()
     }
 }
 
@@ -47,3 +74,206 @@ impl From<&Option<Providers>> for Providers {
         }
     }
 }
+
+impl Providers {
+    // public instance used when the repo has no host
+    fn get_default_host(&self) -> &str {
+        match self {
+            Providers::GitHub => "github.com",
+            Providers::GitLab => "gitlab.com",
+            Providers::Gitee => "gitee.com",
+            Providers::Gitea => "gitea.com",
+            Providers::Forgejo | Providers::Codeberg => "codeberg.org",
+        }
+    }
+
+    // host may be a bare hostname or a base url such as http://git.internal:3000/gitlab
+    fn get_base_url(&self, host: &Option<String>) -> String {
+        let host = host
+            .as_deref()
+            .unwrap_or_else(|| self.get_default_host())
+            .trim_end_matches('/');
+
+        if host.contains("://") {
+            host.to_string()
+        } else {
+            format!("https://{}", host)
+        }
+    }
+
+    pub fn get_token_env_vars(&self) -> &'static [&'static str] {
+        match self {
+            Providers::GitHub => &["GITHUB_TOKEN"],
+            Providers::GitLab => &["GITLAB_TOKEN"],
+            Providers::Gitee => &["GITEE_TOKEN"],
+            Providers::Gitea | Providers::Forgejo | Providers::Codeberg => &["GITEA_TOKEN"],
+        }
+    }
+
+    pub fn get_repo_url(&self, repo: &Repo) -> Result<Url, Box<dyn std::error::Error>> {
+        Ok(Url::parse(&format!(
+            "{}/{}",
+            self.get_base_url(&repo.host),
+            &repo.repo
+        ))?)
+    }
+
+    // release API of the provider on the repo host
+    pub fn get_release_provider(&self, repo: &Repo) -> Box<dyn ReleaseProvider> {
+        let base_url = self.get_base_url(&repo.host);
+        match self {
+            Providers::GitHub => Box::new(GitHubProvider::new(&base_url)),
+            Providers::GitLab => Box::new(GitLabProvider::new(&base_url)),
+            Providers::Gitee => Box::new(GiteeProvider::new(&base_url)),
+            Providers::Gitea | Providers::Forgejo | Providers::Codeberg => {
+                Box::new(GiteaProvider::new(&base_url))
+            }
+        }
+    }
+}
+
+// a forge release API, mapping its responses onto the common release model
+pub trait ReleaseProvider {
+    fn get_releases_url(&self, repo: &str) -> Result<Url, Box<dyn std::error::Error>>;
+
+    // releases listed by the API, newest first
+    fn parse_releases(&self, body: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>>;
+
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request.bearer_auth(token)
+    }
+
+    // next page of the release listing, from the `Link: <url>; rel="next"` header
+    fn get_next_page_url(&self, _url: &Url, headers: &HeaderMap) -> Option<Url> {
+        headers
+            .get_all("link")
+            .iter()
+            .filter_map(|link| link.to_str().ok())
+            .find_map(get_next_link)
+    }
+
+    // whether the first pages hold the newest releases, so the listing can stop at a match
+    fn is_newest_first(&self) -> bool {
+        true
+    }
+}
+
+fn get_next_link(link: &str) -> Option<Url> {
+    link.split(',').find_map(|part| {
+        let mut params = part.split(';').map(str::trim);
+        let url = params.next()?.strip_prefix('<')?.strip_suffix('>')?;
+        params
+            .any(|param| param == "rel=\"next\"" || param == "rel=next")
+            .then(|| Url::parse(url).ok())
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod provider_url_tests {
+    use super::*;
+
+    fn get_repo(repo: &str, provider: &str, host: Option<&str>) -> Repo {
+        serde_yaml::from_str(&format!(
+            "{{ repo: {}, provider: {}, host: {} }}",
+            repo,
+            provider,
+            host.unwrap_or("null")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_default_to_public_hosts() {
+        let repo = get_repo("sharkdp/fd", "github", None);
+        assert_eq!(
+            Providers::GitHub
+                .get_release_provider(&repo)
+                .get_releases_url(&repo.repo)
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/repos/sharkdp/fd/releases"
+        );
+        assert_eq!(
+            Providers::GitHub.get_repo_url(&repo).unwrap().as_str(),
+            "https://github.com/sharkdp/fd"
+        );
+
+        let repo = get_repo("forgejo/forgejo", "codeberg", None);
+        assert_eq!(
+            Providers::Codeberg
+                .get_release_provider(&repo)
+                .get_releases_url(&repo.repo)
+                .unwrap()
+                .as_str(),
+            "https://codeberg.org/api/v1/repos/forgejo/forgejo/releases"
+        );
+    }
+
+    #[test]
+    fn it_should_use_configured_hosts() {
+        let repo = get_repo(
+            "tools/group/cli",
+            "gitlab",
+            Some("http://git.internal:8080/gitlab/"),
+        );
+        assert_eq!(
+            Providers::GitLab
+                .get_release_provider(&repo)
+                .get_releases_url(&repo.repo)
+                .unwrap()
+                .as_str(),
+            "http://git.internal:8080/gitlab/api/v4/projects/tools%2Fgroup%2Fcli/releases"
+        );
+        assert_eq!(
+            Providers::GitLab.get_repo_url(&repo).unwrap().as_str(),
+            "http://git.internal:8080/gitlab/tools/group/cli"
+        );
+
+        let repo = get_repo("org/tool", "github", Some("github.example.com"));
+        assert_eq!(
+            Providers::GitHub
+                .get_release_provider(&repo)
+                .get_releases_url(&repo.repo)
+                .unwrap()
+                .as_str(),
+            "https://github.example.com/api/v3/repos/org/tool/releases"
+        );
+    }
+}
+
+#[cfg(test)]
+mod next_page_url_tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn it_should_follow_link_header() {
+        let repo: Repo = serde_yaml::from_str("{ repo: sharkdp/fd }").unwrap();
+        let provider = Providers::GitHub.get_release_provider(&repo);
+        let url = provider.get_releases_url(&repo.repo).unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(provider.get_next_page_url(&url, &headers), None);
+
+        headers.insert(
+            "link",
+            HeaderValue::from_static(
+                "<https://api.github.com/repositories/1/releases?page=3>; rel=\"next\", \
+                 <https://api.github.com/repositories/1/releases?page=5>; rel=\"last\"",
+            ),
+        );
+        assert_eq!(
+            provider.get_next_page_url(&url, &headers).unwrap().as_str(),
+            "https://api.github.com/repositories/1/releases?page=3"
+        );
+
+        headers.insert(
+            "link",
+            HeaderValue::from_static(
+                "<https://api.github.com/repositories/1/releases?page=1>; rel=\"first\"",
+            ),
+        );
+        assert_eq!(provider.get_next_page_url(&url, &headers), None);
+    }
+}
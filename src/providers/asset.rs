@@ -0,0 +1,516 @@
+use std::fmt;
+
+use log::info;
+use regex::Regex;
+
+use crate::utils::config::{Libc, Repo};
+use crate::utils::platform::{get_arch, get_os};
+
+use super::release::{Release, ReleaseAsset};
+
+// os as named in assets, by os config key
+const OS_NAMES: [(&str, &[&str]); 5] = [
+    ("linux", &["linux", "linux64", "linux32"]),
+    (
+        "macos",
+        &["darwin", "macos", "mac", "osx", "os-x", "apple", "macosx"],
+    ),
+    (
+        "windows",
+        &[
+            "windows", "win", "win32", "win64", "mingw", "mingw32", "mingw64", "msvc", "cygwin",
+        ],
+    ),
+    ("freebsd", &["freebsd"]),
+    ("netbsd", &["netbsd"]),
+];
+
+// arch as named in assets, by arch config key, with 64 bit names checked first
+const ARCH_NAMES: [(&str, &[&str]); 9] = [
+    (
+        "x86_64",
+        &[
+            "x86_64", "x86-64", "amd64", "x64", "intel", "linux64", "win64",
+        ],
+    ),
+    ("aarch64", &["aarch64", "arm64", "armv8"]),
+    ("riscv64", &["riscv64", "riscv64gc"]),
+    ("ppc64le", &["ppc64le", "powerpc64le", "ppc64el"]),
+    ("ppc64", &["ppc64", "powerpc64"]),
+    ("s390x", &["s390x"]),
+    (
+        "i686",
+        &[
+            "i386", "i586", "i686", "386", "x86", "ia32", "win32", "linux32",
+        ],
+    ),
+    ("armv7", &["armv7", "armv7l", "armv7a", "armv7hf", "armhf"]),
+    ("armv6", &["arm", "armv6", "armv6l", "armv6hf", "armel"]),
+];
+
+// archs whose assets also run on another, such as armv6 builds on armv7 boards
+const COMPATIBLE_ARCHES: [(&str, &str); 1] = [("armv7", "armv6")];
+
+// assets accompanying the binaries rather than being one
+const EXCLUDED_ASSETS: [(&str, &str); 5] = [
+    (
+        r"\.(sha\d*|sha\d+sum|md5|b3|sum)$|checksums?|sha\d+sums",
+        "checksum",
+    ),
+    (
+        r"\.(sig|asc|minisig|pem|crt|cert|pub|intoto\.jsonl)$",
+        "signature",
+    ),
+    (r"sbom|\.spdx|\.cdx|provenance|attestation", "SBOM"),
+    (r"(^|[^a-z])(src|source)([^a-z]|$)", "source archive"),
+    (
+        r"\.(pdb|dsym|txt|json|ya?ml|md)$|[-_.](debug|dbg|symbols)([-_.]|$)",
+        "not a binary",
+    ),
+];
+
+// formats by preference, archives first and installers last
+const FORMATS: [(&str, i32, &str); 12] = [
+    (
+        r"\.(tar\.gz|tgz|tar\.xz|txz|tar\.bz2|tbz2?|tar\.zst|tzst)$",
+        30,
+        "tar archive",
+    ),
+    (r"\.zip$", 28, "zip archive"),
+    (r"\.7z$", 20, "7z archive"),
+    (r"\.(gz|xz|bz2|zst)$", 22, "compressed binary"),
+    (r"\.appimage$", 12, "AppImage"),
+    (r"\.(deb|rpm|apk)$|\.pkg\.tar\.(zst|xz)$", 8, "package"),
+    (r"(setup|installer)[^/]*\.exe$", 4, "installer"),
+    (r"\.exe$", 25, "executable"),
+    (r"\.(msi|msix|dmg|pkg)$", 4, "installer"),
+    (
+        r"\.(vsix|whl|jar|nupkg|gem|crate)$",
+        0,
+        "package for another tool",
+    ),
+    (r"\.tar$", 26, "tar archive"),
+    // no extension, or a version such as -v1.2.3
+    (r"(^|[-_/])[^.]+$|\.\d+$", 24, "binary"),
+];
+
+// asset with its score, or the reason it cannot be used on this host
+#[derive(Debug)]
+pub struct AssetCandidate<'a> {
+    pub asset: &'a ReleaseAsset,
+    pub score: i32,
+    pub reasons: Vec<String>,
+    pub rejection: Option<String>,
+}
+
+impl fmt::Display for AssetCandidate<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.rejection {
+            Some(rejection) => write!(f, "{:>4}  {} ({})", "-", self.asset.name, rejection),
+            None => write!(
+                f,
+                "{:>4}  {} ({})",
+                self.score,
+                self.asset.name,
+                self.reasons.join(", ")
+            ),
+        }
+    }
+}
+
+fn is_named(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|candidate| {
+        name.match_indices(candidate).any(|(index, _)| {
+            let before = name[..index].chars().next_back();
+            let after = name[index + candidate.len()..].chars().next();
+            !before.is_some_and(|c| c.is_ascii_alphanumeric())
+                && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+        })
+    })
+}
+
+fn get_named<'a>(name: &str, table: &[(&'a str, &[&str])]) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(_, names)| is_named(name, names))
+        .map(|(named, _)| *named)
+}
+
+fn is_match(pattern: &str, name: &str) -> bool {
+    Regex::new(pattern).is_ok_and(|re| re.is_match(name))
+}
+
+fn get_candidate<'a>(
+    asset: &'a ReleaseAsset,
+    binary_pattern: Option<&Regex>,
+    libc: &Libc,
+    (os, arch): (&str, &str),
+) -> AssetCandidate<'a> {
+    let name = asset.name.to_lowercase();
+    let mut candidate = AssetCandidate {
+        asset,
+        score: 0,
+        reasons: vec![],
+        rejection: None,
+    };
+    let reject = |mut candidate: AssetCandidate<'a>, rejection: String| {
+        candidate.rejection = Some(rejection);
+        candidate
+    };
+
+    // an explicit pattern wins outright, the first matching asset being picked
+    if let Some(re) = binary_pattern {
+        if !re.is_match(&asset.name) {
+            return reject(candidate, "does not match binary_pattern".to_string());
+        }
+        candidate.score = 100;
+        candidate.reasons.push("matches binary_pattern".to_string());
+        return candidate;
+    }
+    if let Some((_, kind)) = EXCLUDED_ASSETS
+        .iter()
+        .find(|(pattern, _)| is_match(pattern, &name))
+    {
+        return reject(candidate, kind.to_string());
+    }
+
+    let named_os = get_named(&name, &OS_NAMES).or(
+        // formats only found on one os
+        if is_match(r"\.(exe|msi|msix)$", &name) {
+            Some("windows")
+        } else if is_match(r"\.(dmg|pkg)$", &name) {
+            Some("macos")
+        } else if is_match(r"\.(deb|rpm|apk|appimage)$", &name) {
+            Some("linux")
+        } else {
+            None
+        },
+    );
+    match named_os {
+        Some(named_os) if named_os != os => {
+            return reject(candidate, format!("built for {}", named_os))
+        }
+        Some(named_os) => {
+            candidate.score += 40;
+            candidate.reasons.push(format!("os {}", named_os));
+        }
+        None => candidate.reasons.push("os not named".to_string()),
+    }
+
+    match get_named(&name, &ARCH_NAMES) {
+        Some(named_arch) if named_arch == arch => {
+            candidate.score += 40;
+            candidate.reasons.push(format!("arch {}", named_arch));
+        }
+        Some(named_arch) if COMPATIBLE_ARCHES.contains(&(arch, named_arch)) => {
+            candidate.score += 30;
+            candidate
+                .reasons
+                .push(format!("arch {}, runs on {}", named_arch, arch));
+        }
+        Some(named_arch) => return reject(candidate, format!("built for {}", named_arch)),
+        None if os == "macos" && is_named(&name, &["universal", "universal2"]) => {
+            candidate.score += 35;
+            candidate.reasons.push("universal".to_string());
+        }
+        None => candidate.reasons.push("arch not named".to_string()),
+    }
+
+    if os == "linux" {
+        let named_libc = if is_named(&name, &["musl", "musleabi", "musleabihf", "static"]) {
+            Some(Libc::Musl)
+        } else if is_named(&name, &["gnu", "gnueabi", "gnueabihf", "glibc"]) {
+            Some(Libc::Gnu)
+        } else {
+            None
+        };
+        match named_libc {
+            Some(named_libc) if &named_libc == libc => {
+                candidate.score += 10;
+                candidate.reasons.push(format!("{} preferred", named_libc));
+            }
+            Some(named_libc) => candidate.reasons.push(named_libc.to_string()),
+            None => candidate.score += 5,
+        }
+    }
+
+    if let Some((_, score, format)) = FORMATS
+        .iter()
+        .find(|(pattern, _, _)| is_match(pattern, &name))
+    {
+        candidate.score += score;
+        candidate.reasons.push(format.to_string());
+    }
+
+    candidate
+}
+
+// assets of the release ranked for the platform, best first, with rejected ones last
+fn rank_assets_for<'a>(
+    release: &'a Release,
+    repo: &Repo,
+    platform: (&str, &str),
+) -> Result<Vec<AssetCandidate<'a>>, Box<dyn std::error::Error>> {
+    let binary_pattern = repo.binary_pattern.as_deref().map(Regex::new).transpose()?;
+    let libc = repo.libc.to_owned().unwrap_or_default();
+
+    let mut candidates = release
+        .assets
+        .iter()
+        .map(|asset| get_candidate(asset, binary_pattern.as_ref(), &libc, platform))
+        .collect::<Vec<AssetCandidate>>();
+    candidates.sort_by_key(|candidate| {
+        (
+            candidate.rejection.is_some(),
+            std::cmp::Reverse(candidate.score),
+        )
+    });
+
+    Ok(candidates)
+}
+
+pub fn rank_assets<'a>(
+    release: &'a Release,
+    repo: &Repo,
+) -> Result<Vec<AssetCandidate<'a>>, Box<dyn std::error::Error>> {
+    rank_assets_for(release, repo, (get_os(), get_arch()))
+}
+
+// best ranked asset of the release for this host
+pub fn get_matched_asset<'a>(
+    release: &'a Release,
+    repo: &Repo,
+) -> Result<&'a ReleaseAsset, Box<dyn std::error::Error>> {
+    let candidates = rank_assets(release, repo)?;
+    info!(
+        "ranked assets of {} {} for {} {}:\n{}",
+        &repo.repo,
+        &release.tag_name,
+        get_os(),
+        get_arch(),
+        candidates
+            .iter()
+            .map(|candidate| candidate.to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    );
+
+    match candidates.first() {
+        Some(candidate) if candidate.rejection.is_none() => Ok(candidate.asset),
+        _ => Err(format!(
+            "no asset of {} {} is compatible with {} {}, see `orbiter resolve` for why",
+            &repo.repo,
+            &release.tag_name,
+            get_os(),
+            get_arch()
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod rank_assets_tests {
+    use super::*;
+
+    const FD_ASSETS: [&str; 12] = [
+        "fd-v10.1.0-aarch64-apple-darwin.tar.gz",
+        "fd-v10.1.0-aarch64-unknown-linux-gnu.tar.gz",
+        "fd-v10.1.0-aarch64-unknown-linux-musl.tar.gz",
+        "fd-v10.1.0-i686-pc-windows-msvc.zip",
+        "fd-v10.1.0-x86_64-apple-darwin.tar.gz",
+        "fd-v10.1.0-x86_64-pc-windows-msvc.zip",
+        "fd-v10.1.0-x86_64-unknown-linux-gnu.tar.gz",
+        "fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz",
+        "fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "fd-musl_10.1.0_amd64.deb",
+        "fd_10.1.0_amd64.deb",
+        "source.tar.gz",
+    ];
+
+    fn get_release(names: &[&str]) -> Release {
+        Release {
+            tag_name: "v10.1.0".to_string(),
+            assets: names
+                .iter()
+                .map(|name| ReleaseAsset {
+                    name: name.to_string(),
+                    url: format!("https://example.com/{}", name),
+                    api_url: None,
+                })
+                .collect(),
+            is_prerelease: false,
+            is_draft: false,
+            published_at: None,
+        }
+    }
+
+    fn get_repo(binary_pattern: Option<&str>, libc: Option<Libc>) -> Repo {
+        serde_yaml::from_str::<Repo>(&format!(
+            "{{ repo: sharkdp/fd, binary_pattern: {} }}",
+            binary_pattern.unwrap_or("null")
+        ))
+        .map(|repo| Repo { libc, ..repo })
+        .unwrap()
+    }
+
+    fn get_best(release: &Release, repo: &Repo, platform: (&str, &str)) -> Option<String> {
+        rank_assets_for(release, repo, platform)
+            .unwrap()
+            .into_iter()
+            .next()
+            .filter(|candidate| candidate.rejection.is_none())
+            .map(|candidate| candidate.asset.name.to_owned())
+    }
+
+    #[test]
+    fn it_should_pick_the_asset_of_the_platform() {
+        let release = get_release(&FD_ASSETS);
+        let repo = get_repo(None, None);
+
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "x86_64")).as_deref(),
+            Some("fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "aarch64")).as_deref(),
+            Some("fd-v10.1.0-aarch64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("macos", "aarch64")).as_deref(),
+            Some("fd-v10.1.0-aarch64-apple-darwin.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("windows", "i686")).as_deref(),
+            Some("fd-v10.1.0-i686-pc-windows-msvc.zip")
+        );
+        assert_eq!(
+            get_best(
+                &release,
+                &get_repo(None, Some(Libc::Gnu)),
+                ("linux", "x86_64")
+            )
+            .as_deref(),
+            Some("fd-v10.1.0-x86_64-unknown-linux-gnu.tar.gz")
+        );
+    }
+
+    #[test]
+    fn it_should_pick_assets_of_less_common_platforms() {
+        let release = get_release(&[
+            "tool-1.0.0-arm-unknown-linux-gnueabihf.tar.gz",
+            "tool-1.0.0-armv7-unknown-linux-musleabihf.tar.gz",
+            "tool-1.0.0-arm64-linux.tar.gz",
+            "tool-1.0.0-powerpc64-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-powerpc64le-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-riscv64gc-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-s390x-unknown-linux-gnu.tar.gz",
+            "tool-1.0.0-x86_64-unknown-freebsd.tar.gz",
+            "tool-1.0.0-amd64-netbsd.tar.gz",
+        ]);
+        let repo = get_repo(None, None);
+
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "armv7")).as_deref(),
+            Some("tool-1.0.0-armv7-unknown-linux-musleabihf.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "ppc64le")).as_deref(),
+            Some("tool-1.0.0-powerpc64le-unknown-linux-gnu.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "riscv64")).as_deref(),
+            Some("tool-1.0.0-riscv64gc-unknown-linux-gnu.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "s390x")).as_deref(),
+            Some("tool-1.0.0-s390x-unknown-linux-gnu.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("freebsd", "x86_64")).as_deref(),
+            Some("tool-1.0.0-x86_64-unknown-freebsd.tar.gz")
+        );
+        assert_eq!(
+            get_best(&release, &repo, ("netbsd", "x86_64")).as_deref(),
+            Some("tool-1.0.0-amd64-netbsd.tar.gz")
+        );
+        assert_eq!(get_best(&release, &repo, ("linux", "i686")), None);
+        assert_eq!(
+            get_best(
+                &get_release(&["tool-1.0.0-arm-unknown-linux-gnueabihf.tar.gz"]),
+                &repo,
+                ("linux", "armv7")
+            )
+            .as_deref(),
+            Some("tool-1.0.0-arm-unknown-linux-gnueabihf.tar.gz")
+        );
+    }
+
+    #[test]
+    fn it_should_reject_incompatible_assets() {
+        let release = get_release(&FD_ASSETS);
+        let candidates =
+            rank_assets_for(&release, &get_repo(None, None), ("linux", "x86_64")).unwrap();
+        let get_rejection = |name: &str| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.asset.name == name)
+                .and_then(|candidate| candidate.rejection.to_owned())
+        };
+
+        assert_eq!(
+            get_rejection("fd-v10.1.0-x86_64-apple-darwin.tar.gz").as_deref(),
+            Some("built for macos")
+        );
+        assert_eq!(
+            get_rejection("fd-v10.1.0-aarch64-unknown-linux-gnu.tar.gz").as_deref(),
+            Some("built for aarch64")
+        );
+        assert_eq!(
+            get_rejection("fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz.sha256").as_deref(),
+            Some("checksum")
+        );
+        assert_eq!(
+            get_rejection("source.tar.gz").as_deref(),
+            Some("source archive")
+        );
+        assert_eq!(
+            get_best(&release, &get_repo(None, None), ("freebsd", "x86_64")),
+            None
+        );
+    }
+
+    #[test]
+    fn it_should_only_pick_assets_matching_binary_pattern() {
+        let release = get_release(&FD_ASSETS);
+        let repo = get_repo(Some(r#"'fd_.*\.deb'"#), None);
+
+        assert_eq!(
+            get_best(&release, &repo, ("linux", "x86_64")).as_deref(),
+            Some("fd_10.1.0_amd64.deb")
+        );
+    }
+
+    #[test]
+    fn it_should_pick_pattern_matches_regardless_of_heuristics() {
+        let release = get_release(&FD_ASSETS);
+
+        assert_eq!(
+            get_best(
+                &release,
+                &get_repo(Some("source"), None),
+                ("linux", "x86_64")
+            )
+            .as_deref(),
+            Some("source.tar.gz")
+        );
+        assert_eq!(
+            get_best(
+                &release,
+                &get_repo(Some("apple-darwin"), None),
+                ("linux", "x86_64")
+            )
+            .as_deref(),
+            Some("fd-v10.1.0-aarch64-apple-darwin.tar.gz")
+        );
+    }
+}
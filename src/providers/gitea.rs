@@ -0,0 +1,98 @@
+use reqwest::blocking::RequestBuilder;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::release::{parse_published_at, Release, ReleaseAsset};
+use super::ReleaseProvider;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteaReleaseAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteaRelease {
+    pub tag_name: String,
+    pub assets: Vec<GiteaReleaseAsset>,
+    pub prerelease: bool,
+    pub draft: bool,
+    pub published_at: Option<String>,
+}
+
+impl From<GiteaRelease> for Release {
+    fn from(release: GiteaRelease) -> Self {
+        Release {
+            is_prerelease: release.prerelease,
+            is_draft: release.draft,
+            published_at: parse_published_at(&release.published_at),
+            tag_name: release.tag_name,
+            assets: release
+                .assets
+                .into_iter()
+                .map(|asset| ReleaseAsset {
+                    name: asset.name,
+                    url: asset.browser_download_url,
+                    api_url: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+// Gitea and its Forgejo fork, as run by Codeberg
+pub struct GiteaProvider {
+    api_url: String,
+}
+
+impl GiteaProvider {
+    pub fn new(base_url: &str) -> Self {
+        GiteaProvider {
+            api_url: format!("{}/api/v1", base_url),
+        }
+    }
+}
+
+impl ReleaseProvider for GiteaProvider {
+    fn get_releases_url(&self, repo: &str) -> Result<Url, Box<dyn std::error::Error>> {
+        Ok(Url::parse(&format!(
+            "{}/repos/{}/releases",
+            self.api_url, repo
+        ))?)
+    }
+
+    fn parse_releases(&self, body: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let releases: Vec<GiteaRelease> = serde_json::from_str(body)?;
+
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request.header("Authorization", format!("token {}", token))
+    }
+}
+
+#[cfg(test)]
+mod gitea_provider_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_releases() {
+        let provider = GiteaProvider::new("https://codeberg.org");
+        let releases = provider
+            .parse_releases(include_str!("../../tests/data/releases/gitea.json"))
+            .unwrap();
+
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].tag_name, "v9.0.0");
+        assert!(!releases[0].is_prerelease && !releases[0].is_draft);
+        assert!(releases[0].published_at.is_some());
+        assert_eq!(releases[0].assets[0].name, "forgejo-9.0.0-linux-amd64.xz");
+        // attachments are served by uuid rather than by name
+        assert_eq!(
+            releases[0].assets[0].url,
+            "https://codeberg.org/attachments/8d6e7c1e-3a1c-4f0e-9b0e-5e6b0f5c2a10"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Reverse;
+
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::HeaderMap;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::release::{parse_published_at, Release, ReleaseAsset};
+use super::ReleaseProvider;
+
+// generated source archives are listed without a name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteeReleaseAsset {
+    name: Option<String>,
+    browser_download_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GiteeRelease {
+    pub id: u64,
+    pub tag_name: String,
+    pub assets: Vec<GiteeReleaseAsset>,
+    pub prerelease: Option<bool>,
+    pub created_at: Option<String>,
+}
+
+impl From<GiteeRelease> for Release {
+    fn from(release: GiteeRelease) -> Self {
+        Release {
+            is_prerelease: release.prerelease.unwrap_or(false),
+            // drafts are not listed
+            is_draft: false,
+            published_at: parse_published_at(&release.created_at),
+            tag_name: release.tag_name,
+            assets: release
+                .assets
+                .into_iter()
+                .filter_map(|asset| {
+                    Some(ReleaseAsset {
+                        name: asset.name?,
+                        url: asset.browser_download_url,
+                        api_url: None,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct GiteeProvider {
+    api_url: String,
+}
+
+impl GiteeProvider {
+    pub fn new(base_url: &str) -> Self {
+        GiteeProvider {
+            api_url: format!("{}/api/v5", base_url),
+        }
+    }
+}
+
+impl ReleaseProvider for GiteeProvider {
+    fn get_releases_url(&self, repo: &str) -> Result<Url, Box<dyn std::error::Error>> {
+        Ok(Url::parse(&format!(
+            "{}/repos/{}/releases",
+            self.api_url, repo
+        ))?)
+    }
+
+    // releases are listed oldest first
+    fn parse_releases(&self, body: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let mut releases: Vec<GiteeRelease> = serde_json::from_str(body)?;
+        releases.sort_by_key(|release| Reverse(release.id));
+
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request.query(&[("access_token", token)])
+    }
+
+    // pages are counted in a `total_page` header instead of a Link header
+    fn get_next_page_url(&self, url: &Url, headers: &HeaderMap) -> Option<Url> {
+        let total_pages: u64 = headers.get("total_page")?.to_str().ok()?.parse().ok()?;
+        let page = url
+            .query_pairs()
+            .find(|(name, _)| name == "page")
+            .and_then(|(_, page)| page.parse().ok())
+            .unwrap_or(1);
+        if page >= total_pages {
+            return None;
+        }
+
+        let mut next_url = url.clone();
+        next_url
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(url.query_pairs().filter(|(name, _)| name != "page"))
+            .append_pair("page", &(page + 1).to_string());
+        Some(next_url)
+    }
+
+    fn is_newest_first(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod gitee_provider_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_releases_newest_first() {
+        let provider = GiteeProvider::new("https://gitee.com");
+        let releases = provider
+            .parse_releases(include_str!("../../tests/data/releases/gitee.json"))
+            .unwrap();
+
+        assert_eq!(
+            releases
+                .iter()
+                .map(|release| release.tag_name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["v1.0.0", "v0.9.0"]
+        );
+        assert_eq!(releases[0].assets.len(), 1);
+        assert!(releases[0].published_at > releases[1].published_at);
+        assert_eq!(releases[0].assets[0].name, "tool-linux-amd64.tar.gz");
+    }
+
+    #[test]
+    fn it_should_count_pages() {
+        let provider = GiteeProvider::new("https://gitee.com");
+        let url = provider.get_releases_url("org/tool").unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(provider.get_next_page_url(&url, &headers), None);
+
+        headers.insert("total_page", "2".parse().unwrap());
+        let next_url = provider.get_next_page_url(&url, &headers).unwrap();
+        assert_eq!(
+            next_url.as_str(),
+            "https://gitee.com/api/v5/repos/org/tool/releases?page=2"
+        );
+        assert_eq!(provider.get_next_page_url(&next_url, &headers), None);
+    }
+}
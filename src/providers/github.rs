@@ -1,18 +1,14 @@
-use regex::Regex;
 use reqwest::Url;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::utils::config::Repo;
-use crate::utils::httpclient::get_binary_pattern_by_arch;
-use crate::utils::httpclient::get_binary_pattern_by_os;
-
-use super::Providers;
+use super::release::{parse_published_at, Release, ReleaseAsset};
+use super::ReleaseProvider;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitHubReleaseAsset {
     name: String,
-    content_type: String,
+    url: String,
     browser_download_url: String,
 }
 
@@ -20,84 +16,90 @@ pub struct GitHubReleaseAsset {
 pub struct GitHubRelease {
     pub tag_name: String,
     pub assets: Vec<GitHubReleaseAsset>,
+    pub prerelease: bool,
+    pub draft: bool,
+    pub published_at: Option<String>, // unset for drafts
+}
+
+impl From<GitHubRelease> for Release {
+    fn from(release: GitHubRelease) -> Self {
+        Release {
+            is_prerelease: release.prerelease,
+            is_draft: release.draft,
+            published_at: parse_published_at(&release.published_at),
+            tag_name: release.tag_name,
+            assets: release
+                .assets
+                .into_iter()
+                .map(|asset| ReleaseAsset {
+                    name: asset.name,
+                    url: asset.browser_download_url,
+                    api_url: Some(asset.url),
+                })
+                .collect(),
+        }
+    }
 }
 
-pub fn get_repo_release_asset_url(repo: &Repo) -> Result<String, Box<dyn std::error::Error>> {
-    let all_release_url = match Providers::from(&repo.provider) {
-        Providers::GitHub => Url::parse(&format!(
-            "https://api.github.com/repos/{}/releases",
-            &repo.repo
-        ))?,
-        Providers::GitLab => Url::parse(&format!(
-            "https://gitlab.com/api/v4/projects/{}/releases",
-            &repo.repo
-        ))?,
-        Providers::Gitee => Url::parse(&format!(
-            "https://gitee.com/api/v5/repos/{}/releases",
-            &repo.repo
-        ))?,
-    };
-    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
-    let res = client
-        .get(all_release_url)
-        .header("Accept", "*/*")
-        .header("User-Agent", "orbiter")
-        .send()?;
+pub struct GitHubProvider {
+    api_url: String,
+}
 
-    let releases: Vec<GitHubRelease> = res.json()?;
-    let release = if let Some(ver) = &repo.ver {
-        releases
-            .iter()
-            .find(|release| release.tag_name.eq_ignore_ascii_case(ver))
-            .unwrap()
-    } else {
-        releases
-            .iter()
-            .find(|release| !release.tag_name.contains("nightly"))
-            .unwrap()
-    };
+impl GitHubProvider {
+    pub fn new(base_url: &str) -> Self {
+        let api_url = if base_url == "https://github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            // GitHub Enterprise Server
+            format!("{}/api/v3", base_url)
+        };
 
-    get_matched_asset_url(&repo.binary_pattern, release)
+        GitHubProvider { api_url }
+    }
 }
 
-fn get_matched_asset_url(
-    binary_pattern: &Option<String>,
-    release: &GitHubRelease,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let assets = release
-        .assets
-        .iter()
-        .filter(|asset| !asset.name.contains("sha256"))
-        .collect::<Vec<&GitHubReleaseAsset>>();
+impl ReleaseProvider for GitHubProvider {
+    fn get_releases_url(&self, repo: &str) -> Result<Url, Box<dyn std::error::Error>> {
+        Ok(Url::parse(&format!(
+            "{}/repos/{}/releases",
+            self.api_url, repo
+        ))?)
+    }
 
-    let matched_assets = if let Some(binary_pattern) = binary_pattern {
-        let re = Regex::new(binary_pattern)?;
-        assets
-            .into_iter()
-            .filter(|asset| re.is_match(&asset.name))
-            .collect::<Vec<&GitHubReleaseAsset>>()
-    } else {
-        let re_os = get_binary_pattern_by_os()?;
-        let re_arch = get_binary_pattern_by_arch()?;
-        let matched_assets = assets
-            .clone()
-            .into_iter()
-            .filter(|asset| {
-                re_os.is_match(&asset.name.to_lowercase())
-                    || re_arch.is_match(&asset.name.to_lowercase())
-            })
-            .collect::<Vec<&GitHubReleaseAsset>>();
+    fn parse_releases(&self, body: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let releases: Vec<GitHubRelease> = serde_json::from_str(body)?;
 
-        if !matched_assets.is_empty() {
-            matched_assets
-        } else {
-            assets.into_iter().collect()
-        }
-    };
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod github_provider_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_releases() {
+        let provider = GitHubProvider::new("https://github.com");
+        let releases = provider
+            .parse_releases(include_str!("../../tests/data/releases/github.json"))
+            .unwrap();
 
-    Ok(matched_assets
-        .first()
-        .unwrap()
-        .browser_download_url
-        .to_owned())
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].tag_name, "v10.2.0");
+        assert_eq!(
+            releases[0].assets[1].name,
+            "fd-v10.2.0-x86_64-unknown-linux-musl.tar.gz"
+        );
+        assert_eq!(
+            releases[0].assets[1].url,
+            "https://github.com/sharkdp/fd/releases/download/v10.2.0/fd-v10.2.0-x86_64-unknown-linux-musl.tar.gz"
+        );
+        assert_eq!(
+            releases[0].assets[1].api_url.as_deref(),
+            Some("https://api.github.com/repos/sharkdp/fd/releases/assets/188661510")
+        );
+        assert!(releases[1].assets.is_empty());
+        assert!(!releases[0].is_prerelease && !releases[0].is_draft);
+        assert!(releases[0].published_at > releases[1].published_at);
+    }
 }
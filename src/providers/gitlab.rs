@@ -0,0 +1,123 @@
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::release::{parse_published_at, Release, ReleaseAsset};
+use super::ReleaseProvider;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitLabReleaseLink {
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitLabReleaseAssets {
+    links: Vec<GitLabReleaseLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitLabRelease {
+    pub tag_name: String,
+    pub assets: GitLabReleaseAssets,
+    pub released_at: Option<String>,
+    pub upcoming_release: Option<bool>, // released_at is in the future
+}
+
+impl From<GitLabRelease> for Release {
+    fn from(release: GitLabRelease) -> Self {
+        Release {
+            // without a pre-release flag, pre-releases are told by their tags
+            is_prerelease: false,
+            is_draft: release.upcoming_release.unwrap_or(false),
+            published_at: parse_published_at(&release.released_at),
+            tag_name: release.tag_name,
+            // sources are the generated source archives, so only links count as assets
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|link| ReleaseAsset {
+                    name: link.name,
+                    url: link.direct_asset_url.unwrap_or(link.url),
+                    api_url: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct GitLabProvider {
+    api_url: String,
+}
+
+impl GitLabProvider {
+    pub fn new(base_url: &str) -> Self {
+        GitLabProvider {
+            api_url: format!("{}/api/v4", base_url),
+        }
+    }
+}
+
+impl ReleaseProvider for GitLabProvider {
+    // project paths including subgroups are passed url encoded
+    fn get_releases_url(&self, repo: &str) -> Result<Url, Box<dyn std::error::Error>> {
+        Ok(Url::parse(&format!(
+            "{}/projects/{}/releases",
+            self.api_url,
+            repo.replace('/', "%2F")
+        ))?)
+    }
+
+    fn parse_releases(&self, body: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let releases: Vec<GitLabRelease> = serde_json::from_str(body)?;
+
+        Ok(releases.into_iter().map(Release::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod gitlab_provider_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_parse_releases() {
+        let provider = GitLabProvider::new("https://gitlab.com");
+        let releases = provider
+            .parse_releases(include_str!("../../tests/data/releases/gitlab.json"))
+            .unwrap();
+
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].tag_name, "v1.46.1");
+        assert!(!releases[0].is_draft);
+        assert!(releases[0].published_at.is_some());
+        assert_eq!(releases[0].assets.len(), 2);
+        assert_eq!(
+            releases[0].assets[0].name,
+            "glab_1.46.1_Linux_x86_64.tar.gz"
+        );
+        assert_eq!(
+            releases[0].assets[0].url,
+            "https://gitlab.com/gitlab-org/cli/-/releases/v1.46.1/downloads/glab_1.46.1_Linux_x86_64.tar.gz"
+        );
+        // links without a direct asset url fall back to the link url
+        assert_eq!(
+            releases[0].assets[1].url,
+            "https://gitlab.com/api/v4/projects/34675721/packages/generic/glab/1.46.1/checksums.txt"
+        );
+    }
+
+    #[test]
+    fn it_should_encode_project_paths() {
+        let provider = GitLabProvider::new("https://gitlab.example.com");
+
+        assert_eq!(
+            provider
+                .get_releases_url("tools/platform/cli")
+                .unwrap()
+                .as_str(),
+            "https://gitlab.example.com/api/v4/projects/tools%2Fplatform%2Fcli/releases"
+        );
+    }
+}
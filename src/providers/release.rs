@@ -0,0 +1,362 @@
+use std::cmp::Reverse;
+
+use regex::Regex;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::Url;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::utils::auth::{get_token, get_token_env_vars};
+use crate::utils::config::Repo;
+use crate::utils::httpclient::{error_for_status, send};
+
+use super::asset::get_matched_asset;
+use super::version::{filter_tags, find_matching_tag, parse_tag_version};
+use super::Providers;
+
+// release as returned by any of the providers
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<ReleaseAsset>,
+    pub is_prerelease: bool,
+    pub is_draft: bool,
+    pub published_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub url: String,
+    pub api_url: Option<String>, // for providers serving private assets through the API only
+}
+
+#[derive(Debug)]
+pub struct ResolvedReleaseAsset {
+    pub tag_name: String,
+    pub published_at: Option<OffsetDateTime>,
+    pub url: String,
+    pub checksum_url: Option<String>,
+}
+
+pub fn parse_published_at(published_at: &Option<String>) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(published_at.as_deref()?, &Rfc3339).ok()
+}
+
+pub fn get_repo_release_asset_url(repo: &Repo) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(get_repo_release_asset(repo)?.url)
+}
+
+// web and API hosts of the repo provider, which are trusted with its token
+fn get_provider_hosts(repo: &Repo) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let provider = Providers::from(&repo.provider);
+    let urls = [
+        provider.get_repo_url(repo)?,
+        provider
+            .get_release_provider(repo)
+            .get_releases_url(&repo.repo)?,
+    ];
+
+    Ok(urls
+        .iter()
+        .filter_map(|url| url.host_str().map(str::to_owned))
+        .collect())
+}
+
+fn get_repo_token(repo: &Repo) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    Ok(get_token(repo, &get_provider_hosts(repo)?))
+}
+
+// request carrying the repo token, unless the url is served by another host
+pub fn get_provider_request(
+    repo: &Repo,
+    url: &str,
+) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
+    let request = client.get(url).header("User-Agent", "orbiter");
+
+    let is_provider_host = Url::parse(url)?
+        .host_str()
+        .map(|host| get_provider_hosts(repo).map(|hosts| hosts.iter().any(|h| h == host)))
+        .transpose()?
+        .unwrap_or(false);
+    if !is_provider_host {
+        return Ok(request);
+    }
+
+    Ok(match get_repo_token(repo)? {
+        Some(token) => Providers::from(&repo.provider)
+            .get_release_provider(repo)
+            .authorize(request, &token),
+        None => request,
+    })
+}
+
+fn get_releases_page(repo: &Repo, url: &Url) -> Result<Response, Box<dyn std::error::Error>> {
+    let res = send(get_provider_request(repo, url.as_str())?.header("Accept", "*/*"))?;
+
+    // private repos are reported as not found to anonymous requests
+    match (error_for_status(res), get_repo_token(repo)?) {
+        (Ok(res), _) => Ok(res),
+        (Err(err), Some(_)) => Err(err),
+        (Err(err), None) => Err(format!(
+            "{}, authenticate with {}token_env or a ~/.netrc entry",
+            err,
+            get_token_env_vars(repo)
+                .iter()
+                .map(|name| format!("{}, ", name))
+                .collect::<String>()
+        )
+        .into()),
+    }
+}
+
+// releases a repo follows when no ver is given
+enum Channel {
+    Stable,
+    Prerelease, // newest of stable and pre-releases
+    Nightly,
+    Tag(Regex),
+}
+
+impl Channel {
+    fn parse(channel: &Option<String>) -> Result<Channel, Box<dyn std::error::Error>> {
+        Ok(match channel.as_deref() {
+            None | Some("stable") => Channel::Stable,
+            Some("prerelease") => Channel::Prerelease,
+            Some("nightly") => Channel::Nightly,
+            Some(tag_pattern) => Channel::Tag(Regex::new(tag_pattern)?),
+        })
+    }
+
+    fn contains(&self, release: &Release) -> bool {
+        let is_nightly = release.tag_name.to_lowercase().contains("nightly");
+        match self {
+            Channel::Stable => !is_nightly && !is_prerelease(release),
+            Channel::Prerelease => !is_nightly,
+            Channel::Nightly => is_nightly,
+            Channel::Tag(re) => re.is_match(&release.tag_name),
+        }
+    }
+}
+
+// flagged as a pre-release, or tagged with a pre-release version such as v1.0.0-rc.1
+fn is_prerelease(release: &Release) -> bool {
+    release.is_prerelease
+        || parse_tag_version(&release.tag_name).is_some_and(|version| !version.pre.is_empty())
+}
+
+// release matching ver, otherwise the newest published release on the channel
+fn select_release<'a>(
+    releases: &'a [Release],
+    repo: &Repo,
+) -> Result<&'a Release, Box<dyn std::error::Error>> {
+    let channel = Channel::parse(&repo.channel)?;
+    let mut candidates = releases
+        .iter()
+        .filter(|release| !release.is_draft)
+        .filter(|release| repo.ver.is_some() || channel.contains(release))
+        .collect::<Vec<&Release>>();
+    // undated releases keep their listed order, after the dated ones
+    candidates.sort_by_key(|release| Reverse(release.published_at));
+
+    let tags = filter_tags(
+        &candidates
+            .iter()
+            .map(|release| release.tag_name.as_str())
+            .collect::<Vec<&str>>(),
+        &repo.tag_pattern,
+    )?;
+    let tag = if let Some(ver) = &repo.ver {
+        find_matching_tag(&tags, ver)
+            .ok_or_else(|| format!("no release matching {} found in {}", ver, &repo.repo))?
+    } else {
+        tags.first().copied().ok_or_else(|| {
+            format!(
+                "no {} release found in {}",
+                repo.channel.as_deref().unwrap_or("stable"),
+                &repo.repo
+            )
+        })?
+    };
+
+    candidates
+        .into_iter()
+        .find(|release| release.tag_name == tag)
+        .ok_or_else(|| format!("release {} not found in {}", tag, &repo.repo).into())
+}
+
+// release of the repo selected by its channel, ver and tag_pattern
+pub fn get_repo_release(repo: &Repo) -> Result<Release, Box<dyn std::error::Error>> {
+    let release_provider = Providers::from(&repo.provider).get_release_provider(repo);
+    let mut releases = vec![];
+    let mut page_url = Some(release_provider.get_releases_url(&repo.repo)?);
+
+    while let Some(url) = page_url {
+        let res = get_releases_page(repo, &url)?;
+        page_url = release_provider.get_next_page_url(&url, res.headers());
+        releases.extend(release_provider.parse_releases(&res.text()?)?);
+
+        // later pages only hold older releases once a match is found
+        if page_url.is_some() && release_provider.is_newest_first() {
+            if let Ok(release) = select_release(&releases, repo) {
+                return Ok(release.to_owned());
+            }
+        }
+    }
+
+    Ok(select_release(&releases, repo)?.to_owned())
+}
+
+pub fn get_repo_release_asset(
+    repo: &Repo,
+) -> Result<ResolvedReleaseAsset, Box<dyn std::error::Error>> {
+    let release = &get_repo_release(repo)?;
+
+    // authenticated downloads go through the API, as private assets are only served there
+    let is_authenticated = get_repo_token(repo)?.is_some();
+    let get_download_url = |asset: &ReleaseAsset| match &asset.api_url {
+        Some(api_url) if is_authenticated => api_url.to_owned(),
+        _ => asset.url.to_owned(),
+    };
+
+    let asset = get_matched_asset(release, repo)?;
+
+    Ok(ResolvedReleaseAsset {
+        tag_name: release.tag_name.to_owned(),
+        published_at: release.published_at,
+        url: get_download_url(asset),
+        checksum_url: get_checksum_asset(&asset.name, release).map(get_download_url),
+    })
+}
+
+// sibling `<asset>.sha256`/`<asset>.sha512`, otherwise a release wide `SHA256SUMS` or
+// `<tool>_<ver>_checksums.txt` listing
+fn get_checksum_asset<'a>(asset_name: &str, release: &'a Release) -> Option<&'a ReleaseAsset> {
+    let sibling_names = ["sha256", "sha256sum", "sha512", "sha512sum"]
+        .iter()
+        .map(|ext| format!("{}.{}", asset_name, ext))
+        .collect::<Vec<String>>();
+    let re_sums = Regex::new(r"(?i)^(.*[-_.])?(sha256|sha512)?(sums|checksums)(\.txt)?$").ok()?;
+
+    release
+        .assets
+        .iter()
+        .find(|asset| sibling_names.contains(&asset.name))
+        .or_else(|| {
+            release
+                .assets
+                .iter()
+                .find(|asset| re_sums.is_match(&asset.name))
+        })
+}
+
+#[cfg(test)]
+mod select_release_tests {
+    use super::*;
+
+    fn get_release(tag_name: &str, is_prerelease: bool, published_at: &str) -> Release {
+        Release {
+            tag_name: tag_name.to_string(),
+            assets: vec![],
+            is_prerelease,
+            is_draft: false,
+            published_at: parse_published_at(&Some(published_at.to_string())),
+        }
+    }
+
+    fn get_releases() -> Vec<Release> {
+        vec![
+            get_release("v0.11.0-rc.1", false, "2024-10-01T00:00:00Z"),
+            get_release("v0.10.2", false, "2024-09-01T00:00:00Z"),
+            get_release("v0.11.0-beta", true, "2024-08-01T00:00:00Z"),
+            // republished nightly, listed in the position of its first release
+            get_release("nightly", true, "2024-10-18T00:00:00Z"),
+            get_release("v0.10.1", false, "2024-07-01T00:00:00Z"),
+        ]
+    }
+
+    fn get_repo(options: &str) -> Repo {
+        serde_yaml::from_str(&format!("{{ repo: neovim/neovim, {} }}", options)).unwrap()
+    }
+
+    #[test]
+    fn it_should_select_releases_by_channel() {
+        let releases = get_releases();
+        let select = |options: &str| {
+            select_release(&releases, &get_repo(options))
+                .unwrap()
+                .tag_name
+                .to_owned()
+        };
+
+        assert_eq!(select("from_release: true"), "v0.10.2");
+        assert_eq!(select("channel: prerelease"), "v0.11.0-rc.1");
+        assert_eq!(select("channel: nightly"), "nightly");
+        assert_eq!(select("channel: '^v0\\.10\\.1$'"), "v0.10.1");
+        assert_eq!(select("channel: nightly, ver: ^0.10"), "v0.10.2");
+    }
+
+    #[test]
+    fn it_should_skip_drafts() {
+        let mut releases = get_releases();
+        releases[1].is_draft = true;
+
+        assert_eq!(
+            select_release(&releases, &get_repo("ver: v0.10.2"))
+                .unwrap_err()
+                .to_string(),
+            "no release matching v0.10.2 found in neovim/neovim"
+        );
+    }
+}
+
+#[cfg(test)]
+mod get_checksum_asset_tests {
+    use super::*;
+
+    fn get_release(asset_names: &[&str]) -> Release {
+        Release {
+            tag_name: "v1.0.0".to_string(),
+            assets: asset_names
+                .iter()
+                .map(|name| ReleaseAsset {
+                    name: name.to_string(),
+                    url: format!("https://example.com/{}", name),
+                    api_url: None,
+                })
+                .collect(),
+            is_prerelease: false,
+            is_draft: false,
+            published_at: None,
+        }
+    }
+
+    fn get_checksum_name(asset_names: &[&str]) -> Option<String> {
+        get_checksum_asset("tool.tar.gz", &get_release(asset_names)).map(|asset| asset.name.clone())
+    }
+
+    #[test]
+    fn it_should_prefer_sibling_checksums() {
+        assert_eq!(
+            get_checksum_name(&["tool.tar.gz", "SHA256SUMS", "tool.tar.gz.sha256"]),
+            Some("tool.tar.gz.sha256".to_string())
+        );
+        assert_eq!(
+            get_checksum_name(&["tool.tar.gz", "tool_1.0.0_checksums.txt"]),
+            Some("tool_1.0.0_checksums.txt".to_string())
+        );
+        assert_eq!(
+            get_checksum_name(&["tool.tar.gz", "sha512sums.txt"]),
+            Some("sha512sums.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_ignore_names_only_containing_checksums() {
+        assert_eq!(
+            get_checksum_name(&["tool.tar.gz", "checksums-notes.md", "checksumsfoo.txt"]),
+            None
+        );
+    }
+}
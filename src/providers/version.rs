@@ -0,0 +1,139 @@
+use regex::Regex;
+use semver::{Version, VersionReq};
+
+// version in tags such as `v1.2.3`, `cli-v2.3.1`, `jq-1.7` or `v1.0.0-rc.1`
+pub fn parse_tag_version(tag: &str) -> Option<Version> {
+    let re_version = Regex::new(
+        r"(\d+)\.(\d+)(?:\.(\d+))?(?:-([0-9A-Za-z][0-9A-Za-z.-]*))?(?:\+[0-9A-Za-z.-]+)?$",
+    )
+    .ok()?;
+    let captures = re_version.captures(tag)?;
+    let core = format!(
+        "{}.{}.{}",
+        &captures[1],
+        &captures[2],
+        captures.get(3).map_or("0", |patch| patch.as_str())
+    );
+
+    match captures.get(4) {
+        Some(pre) => Version::parse(&format!("{}-{}", core, pre.as_str()))
+            .or_else(|_| Version::parse(&core))
+            .ok(),
+        None => Version::parse(&core).ok(),
+    }
+}
+
+// semver requirement such as `^0.40`, `~1.2` or `>=14, <15`, tolerating `v` prefixed versions
+pub fn parse_version_req(ver: &str) -> Option<VersionReq> {
+    let re_prefix = Regex::new(r"(^|[\s,=<>^~])v(\d)").ok()?;
+
+    VersionReq::parse(&re_prefix.replace_all(ver.trim(), "$1$2")).ok()
+}
+
+// whether ver is a semver requirement such as `^0.40` or `2`, standing for a range of
+// versions rather than a single tag, branch or commit such as `v0.40.2` or `nightly`
+pub fn is_version_req(ver: &str) -> bool {
+    let re_version =
+        Regex::new(r"^v?\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?$").unwrap();
+
+    parse_version_req(ver).is_some() && !re_version.is_match(ver.trim())
+}
+
+// tag equal to ver, otherwise the highest tag version satisfying ver as a semver requirement
+pub fn find_matching_tag<'a>(tags: &[&'a str], ver: &str) -> Option<&'a str> {
+    if let Some(tag) = tags.iter().find(|tag| tag.eq_ignore_ascii_case(ver)) {
+        return Some(tag);
+    }
+
+    let version_req = parse_version_req(ver)?;
+    tags.iter()
+        .filter_map(|tag| parse_tag_version(tag).map(|version| (version, *tag)))
+        .filter(|(version, _)| version_req.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+// tags matching the tag pattern, if any
+pub fn filter_tags<'a>(
+    tags: &[&'a str],
+    tag_pattern: &Option<String>,
+) -> Result<Vec<&'a str>, Box<dyn std::error::Error>> {
+    match tag_pattern {
+        Some(tag_pattern) => {
+            let re = Regex::new(tag_pattern)?;
+            Ok(tags
+                .iter()
+                .copied()
+                .filter(|tag| re.is_match(tag))
+                .collect())
+        }
+        None => Ok(tags.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod find_matching_tag_tests {
+    use super::*;
+
+    const TAGS: [&str; 8] = [
+        "nightly",
+        "v0.41.0-rc.1",
+        "v0.40.2",
+        "v0.40.1",
+        "cli-v2.3.1",
+        "v0.39.0",
+        "14.2.0",
+        "jq-1.7",
+    ];
+
+    #[test]
+    fn it_should_parse_tag_versions() {
+        assert_eq!(parse_tag_version("cli-v2.3.1"), Some(Version::new(2, 3, 1)));
+        assert_eq!(parse_tag_version("jq-1.7"), Some(Version::new(1, 7, 0)));
+        assert_eq!(
+            parse_tag_version("v0.41.0-rc.1"),
+            Some(Version::parse("0.41.0-rc.1").unwrap())
+        );
+        assert_eq!(parse_tag_version("nightly"), None);
+    }
+
+    #[test]
+    fn it_should_prefer_exact_tags() {
+        assert_eq!(find_matching_tag(&TAGS, "v0.40.1"), Some("v0.40.1"));
+        assert_eq!(find_matching_tag(&TAGS, "nightly"), Some("nightly"));
+    }
+
+    #[test]
+    fn it_should_pick_the_highest_matching_version() {
+        assert_eq!(find_matching_tag(&TAGS, "^0.40"), Some("v0.40.2"));
+        assert_eq!(find_matching_tag(&TAGS, "~v0.39"), Some("v0.39.0"));
+        assert_eq!(find_matching_tag(&TAGS, ">=14, <15"), Some("14.2.0"));
+        assert_eq!(find_matching_tag(&TAGS, "2"), Some("cli-v2.3.1"));
+        assert_eq!(find_matching_tag(&TAGS, "^0.42"), None);
+    }
+
+    #[test]
+    fn it_should_tell_requirements_from_versions() {
+        for ver in ["^0.40", "~v0.39", ">=14, <15", "2", "1.x", "*"] {
+            assert!(is_version_req(ver), "{}", ver);
+        }
+        for ver in [
+            "v0.40.1",
+            "14.2.0",
+            "v0.41.0-rc.1",
+            "nightly",
+            "main",
+            "a1b2c3d",
+        ] {
+            assert!(!is_version_req(ver), "{}", ver);
+        }
+    }
+
+    #[test]
+    fn it_should_filter_tags_by_pattern() {
+        let tags = filter_tags(&TAGS, &Some("^v0\\.".to_string())).unwrap();
+
+        assert_eq!(find_matching_tag(&tags, "*"), Some("v0.40.2"));
+        assert_eq!(find_matching_tag(&tags, "2"), None);
+    }
+}
@@ -1,13 +1,27 @@
+pub mod auth;
+pub mod check;
+pub mod checksum;
 pub mod cli;
 pub mod completion;
+pub mod condition;
 pub mod config;
+pub mod dependency;
+pub mod download_cache;
 pub mod evaluatable;
 pub mod httpclient;
+pub mod init_cache;
 pub mod listing;
+pub mod lockfile;
 pub mod paths;
 pub mod pipeline;
+pub mod platform;
+pub mod resolve;
 pub mod script;
 pub mod shells;
 pub mod shim;
+pub mod signature;
+pub mod state;
 pub mod symlink;
+pub mod template;
+pub mod uninstall;
 pub mod update;
@@ -0,0 +1,133 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::providers::Providers;
+
+use super::config::Repo;
+
+fn get_netrc_path() -> Option<PathBuf> {
+    match env::var_os("NETRC") {
+        Some(netrc_path) => Some(PathBuf::from(netrc_path)),
+        None => dirs::home_dir().map(|home| home.join(".netrc")),
+    }
+}
+
+// password of the machine entry for the host, otherwise of the default entry if allowed
+pub fn find_netrc_password(netrc: &str, host: &str, is_default_allowed: bool) -> Option<String> {
+    let mut tokens = netrc.split_whitespace();
+    let mut is_matched = false;
+    let mut is_default = false;
+    let mut default_password = None;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                is_matched = tokens.next() == Some(host);
+                is_default = false;
+            }
+            "default" => {
+                is_matched = false;
+                is_default = true;
+            }
+            "password" => match tokens.next() {
+                Some(password) if is_matched => return Some(password.to_string()),
+                Some(password) if is_default && is_default_allowed => {
+                    default_password = Some(password.to_string())
+                }
+                _ => {}
+            },
+            "login" | "account" | "macdef" => {
+                tokens.next();
+            }
+            _ => {}
+        }
+    }
+
+    default_password
+}
+
+fn get_env_token(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|token| !token.is_empty())
+}
+
+// env vars holding a token for the repo provider; only used with its public host,
+// so that a config setting `host:` elsewhere cannot collect the token
+pub fn get_token_env_vars(repo: &Repo) -> &'static [&'static str] {
+    match repo.host {
+        Some(_) => &[],
+        None => Providers::from(&repo.provider).get_token_env_vars(),
+    }
+}
+
+// token for the repo from its token_env, the provider env vars or ~/.netrc entries of its hosts
+pub fn get_token(repo: &Repo, hosts: &[String]) -> Option<String> {
+    if let Some(token) = repo.token_env.as_deref().and_then(get_env_token) {
+        return Some(token);
+    }
+
+    if let Some(token) = get_token_env_vars(repo)
+        .iter()
+        .find_map(|name| get_env_token(name))
+    {
+        return Some(token);
+    }
+
+    // custom hosts need a machine entry of their own
+    let netrc = fs::read_to_string(get_netrc_path()?).ok()?;
+    hosts
+        .iter()
+        .find_map(|host| find_netrc_password(&netrc, host, repo.host.is_none()))
+}
+
+#[cfg(test)]
+mod netrc_tests {
+    use super::*;
+
+    const NETRC: &str = r#"
+machine gitlab.example.com
+  login oauth2
+  password glpat-example
+machine api.github.com login octocat password ghp_example
+default login anonymous password anonymous-token
+"#;
+
+    #[test]
+    fn it_should_find_machine_passwords() {
+        assert_eq!(
+            find_netrc_password(NETRC, "gitlab.example.com", false),
+            Some("glpat-example".to_string())
+        );
+        assert_eq!(
+            find_netrc_password(NETRC, "api.github.com", true),
+            Some("ghp_example".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_default_entry() {
+        assert_eq!(
+            find_netrc_password(NETRC, "codeberg.org", true),
+            Some("anonymous-token".to_string())
+        );
+        assert_eq!(find_netrc_password(NETRC, "codeberg.org", false), None);
+        assert_eq!(
+            find_netrc_password("machine a.example.com password a", "b.example.com", true),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod token_env_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_keep_provider_tokens_to_public_hosts() {
+        let repo: Repo = serde_yaml::from_str("{ repo: org/tool }").unwrap();
+        assert_eq!(get_token_env_vars(&repo), &["GITHUB_TOKEN"]);
+
+        let repo: Repo = serde_yaml::from_str("{ repo: org/tool, host: evil.example }").unwrap();
+        assert!(get_token_env_vars(&repo).is_empty());
+    }
+}
@@ -0,0 +1,830 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+use super::config::{get_conf_d_files, get_config_keys, load_config, resolve_include, ConfigKeys};
+use super::paths::{get_conf_d_dir_path, get_config_path, get_dir};
+
+// a problem found in a config file
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub position: Option<(usize, usize)>, // line and column, starting from 1
+    pub payload_id: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(f, ": ")?;
+        if let Some(payload_id) = &self.payload_id {
+            write!(f, "payload `{}`: ", payload_id)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Debug)]
+enum NodeValue {
+    Scalar(String, TScalarStyle),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+    Alias,
+}
+
+// yaml node along with where it starts in the file
+#[derive(Debug)]
+struct Node {
+    value: NodeValue,
+    line: usize,
+    column: usize,
+}
+
+impl Node {
+    fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            NodeValue::Scalar(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(&self.value, NodeValue::Scalar(value, TScalarStyle::Plain) if value.is_empty() || value == "~" || value == "null")
+    }
+
+    fn get(&self, key: &str) -> Option<&Node> {
+        match &self.value {
+            NodeValue::Mapping(entries) => entries
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn get_key(&self, key: &str) -> Option<&Node> {
+        match &self.value {
+            NodeValue::Mapping(entries) => entries
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(k, _)| k),
+            _ => None,
+        }
+    }
+
+    fn has_any(&self, keys: &[String]) -> bool {
+        keys.iter().any(|key| self.get(key).is_some())
+    }
+}
+
+enum Frame {
+    Sequence(Vec<Node>, Marker),
+    Mapping(Vec<(Node, Node)>, Option<Node>, Marker),
+}
+
+// builds a tree of nodes out of the parser events
+#[derive(Default)]
+struct TreeBuilder {
+    frames: Vec<Frame>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn push_node(&mut self, node: Node) {
+        match self.frames.last_mut() {
+            None => self.root = Some(node),
+            Some(Frame::Sequence(nodes, _)) => nodes.push(node),
+            Some(Frame::Mapping(entries, pending_key, _)) => match pending_key.take() {
+                Some(key) => entries.push((key, node)),
+                None => *pending_key = Some(node),
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for TreeBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let new_node = |value| Node {
+            value,
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+
+        match event {
+            Event::Scalar(value, style, ..) => {
+                self.push_node(new_node(NodeValue::Scalar(value, style)))
+            }
+            Event::Alias(_) => self.push_node(new_node(NodeValue::Alias)),
+            Event::SequenceStart(..) => self.frames.push(Frame::Sequence(vec![], mark)),
+            Event::MappingStart(..) => self.frames.push(Frame::Mapping(vec![], None, mark)),
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (value, start) = match self.frames.pop() {
+                    Some(Frame::Sequence(nodes, start)) => (NodeValue::Sequence(nodes), start),
+                    Some(Frame::Mapping(entries, _, start)) => (NodeValue::Mapping(entries), start),
+                    None => return,
+                };
+                // block mappings are located at their first key
+                let (line, column) = match &value {
+                    NodeValue::Mapping(entries) if !entries.is_empty() => {
+                        (entries[0].0.line, entries[0].0.column)
+                    }
+                    _ => (start.line(), start.col() + 1),
+                };
+                self.push_node(Node {
+                    value,
+                    line,
+                    column,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_known(key: &str, known_keys: &[String]) -> bool {
+    known_keys.iter().any(|known_key| known_key == key)
+}
+
+// the closest known key to a misspelt one
+fn get_suggestion<'a>(key: &str, known_keys: &'a [String]) -> Option<&'a str> {
+    known_keys
+        .iter()
+        .map(|known_key| (known_key.as_str(), strsim::jaro_winkler(key, known_key)))
+        .filter(|(_, similarity)| *similarity >= 0.8)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(known_key, _)| known_key)
+}
+
+struct Checker<'a> {
+    file: &'a Path,
+    keys: &'a ConfigKeys,
+    payload_id: Option<String>,
+    diagnostics: Vec<Diagnostic>,
+    includes: Vec<PathBuf>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, node: &Node, message: String) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.to_path_buf(),
+            position: Some((node.line, node.column)),
+            payload_id: self.payload_id.to_owned(),
+            message,
+        });
+    }
+
+    // report unknown keys, returning the mapping entries if the node is a mapping
+    fn check_keys<'n>(
+        &mut self,
+        node: &'n Node,
+        name: &str,
+        known_keys: &[String],
+    ) -> Option<&'n [(Node, Node)]> {
+        let entries = match &node.value {
+            NodeValue::Mapping(entries) => entries,
+            _ => {
+                self.report(node, format!("`{}` should be a map", name));
+                return None;
+            }
+        };
+
+        for (key, _) in entries {
+            if !is_known(key.as_str().unwrap_or_default(), known_keys) {
+                self.report_unknown_key(key, name, known_keys);
+            }
+        }
+
+        Some(entries)
+    }
+
+    fn report_unknown_key(&mut self, key: &Node, name: &str, known_keys: &[String]) {
+        let key_name = key.as_str().unwrap_or_default();
+        let message = match get_suggestion(key_name, known_keys) {
+            Some(suggestion) => format!(
+                "unknown key `{}` in `{}`, did you mean `{}`?",
+                key_name, name, suggestion
+            ),
+            None => format!(
+                "unknown key `{}` in `{}`, expected one of {}",
+                key_name,
+                name,
+                known_keys.join(", ")
+            ),
+        };
+
+        self.report(key, message);
+    }
+
+    fn check_required(&mut self, node: &Node, name: &str, key: &str) {
+        if node.get(key).is_none() {
+            self.report(node, format!("`{}` is missing `{}`", name, key));
+        }
+    }
+
+    fn check_string(&mut self, node: &Node, name: &str) {
+        match &node.value {
+            NodeValue::Scalar(value, TScalarStyle::Plain)
+                if value.parse::<f64>().is_ok() || value == "true" || value == "false" =>
+            {
+                self.report(
+                    node,
+                    format!(
+                        "`{}` should be a string, quote `{}` to use it as one",
+                        name, value
+                    ),
+                )
+            }
+            NodeValue::Scalar(..) | NodeValue::Alias => {}
+            _ => self.report(node, format!("`{}` should be a string", name)),
+        }
+    }
+
+    fn check_libc(&mut self, node: &Node, name: &str) {
+        let libcs = &self.keys.libcs;
+        if !is_known(node.as_str().unwrap_or_default(), libcs) {
+            let message = format!("`{}` should be {}", name, libcs.join(" or "));
+            self.report(node, message);
+        }
+    }
+
+    fn check_count(&mut self, node: &Node, name: &str) {
+        if node
+            .as_str()
+            .and_then(|value| value.parse::<usize>().ok())
+            .is_none()
+        {
+            self.report(node, format!("`{}` should be a whole number", name));
+        }
+    }
+
+    fn check_bool(&mut self, node: &Node, name: &str) {
+        if !node.is_null() && !matches!(node.as_str(), Some("true") | Some("false")) {
+            self.report(node, format!("`{}` should be true or false", name));
+        }
+    }
+
+    fn check_string_or_list(&mut self, node: &Node, name: &str) {
+        match &node.value {
+            NodeValue::Sequence(nodes) => {
+                for node in nodes {
+                    self.check_string(node, name);
+                }
+            }
+            _ => self.check_string(node, name),
+        }
+    }
+
+    // check each entry of a map against its expected keys
+    fn check_entries(
+        &mut self,
+        node: &Node,
+        name: &str,
+        known_keys: &[String],
+        check_value: fn(&mut Self, &Node, &str),
+    ) {
+        if node.is_null() {
+            return;
+        }
+
+        if let Some(entries) = self.check_keys(node, name, known_keys) {
+            for (key, value) in entries {
+                if let Some(key_name) = key.as_str() {
+                    if is_known(key_name, known_keys) && !value.is_null() {
+                        check_value(self, value, &format!("{}.{}", name, key_name));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_regex(&mut self, node: &Node, name: &str) {
+        let placeholder = Regex::new(r"\{[A-Za-z_][A-Za-z0-9_.]*\}").unwrap();
+        if let Some(pattern) = node.as_str() {
+            // variables are interpolated before the pattern is used
+            if let Err(err) = Regex::new(&placeholder.replace_all(pattern, "var")) {
+                self.report(node, format!("invalid regex in `{}`: {}", name, err));
+            }
+        }
+    }
+
+    fn check_resource(&mut self, node: &Node, name: &str) {
+        let keys = self.keys;
+        if let NodeValue::Scalar(..) = node.value {
+            return self.check_string(node, name);
+        }
+
+        if node.get("url").is_some() {
+            // a location with repo options
+            if let Some(repo) = node.get_key("repo") {
+                self.report(repo, format!("`{}` has both a `url` and a `repo`", name));
+            }
+            for key in keys
+                .repo
+                .iter()
+                .filter(|key| !["repo", "checksum", "signature"].contains(&key.as_str()))
+            {
+                if let Some(option) = node.get_key(key) {
+                    self.report(
+                        option,
+                        format!(
+                            "`{}` only applies to repo resources, not to `url` ones",
+                            key
+                        ),
+                    );
+                }
+            }
+
+            let known_keys = [&keys.verified_location[..], &keys.repo[..]].concat();
+            self.check_keys(node, name, &known_keys);
+            self.check_string(node.get("url").unwrap(), &format!("{}.url", name));
+            self.check_signature(node, name);
+            return;
+        }
+
+        if self.check_keys(node, name, &keys.repo).is_none() {
+            return;
+        }
+        if node.get("repo").is_none() {
+            return self.report(node, format!("`{}` needs either a `url` or a `repo`", name));
+        }
+
+        for key in [
+            "repo",
+            "host",
+            "token_env",
+            "ver",
+            "tag_pattern",
+            "channel",
+            "binary_pattern",
+            "checksum",
+        ] {
+            if let Some(value) = node.get(key) {
+                self.check_string(value, &format!("{}.{}", name, key));
+            }
+        }
+        if let Some(libc) = node.get("libc") {
+            self.check_libc(libc, &format!("{}.libc", name));
+        }
+        if let Some(from_release) = node.get("from_release") {
+            self.check_bool(from_release, &format!("{}.from_release", name));
+        }
+        if let Some(provider) = node.get("provider") {
+            let provider_name = provider.as_str().unwrap_or_default();
+            if !is_known(provider_name, &keys.providers) {
+                let suggestion = get_suggestion(provider_name, &keys.providers)
+                    .map(|suggestion| format!(", did you mean `{}`?", suggestion))
+                    .unwrap_or_default();
+                self.report(
+                    provider,
+                    format!("unknown provider `{}`{}", provider_name, suggestion),
+                );
+            }
+        }
+        for key in ["tag_pattern", "binary_pattern"] {
+            if let Some(pattern) = node.get(key) {
+                self.check_regex(pattern, &format!("{}.{}", name, key));
+            }
+        }
+        self.check_signature(node, name);
+    }
+
+    fn check_signature(&mut self, node: &Node, name: &str) {
+        if let Some(signature) = node.get("signature") {
+            self.check_entries(
+                signature,
+                &format!("{}.signature", name),
+                &self.keys.signature,
+                Self::check_string,
+            );
+        }
+    }
+
+    fn check_arch_resource(&mut self, node: &Node, name: &str) {
+        if node.has_any(&self.keys.arch) {
+            self.check_entries(node, name, &self.keys.arch, Self::check_resource);
+        } else {
+            self.check_resource(node, name);
+        }
+    }
+
+    fn check_adaptive_resource(&mut self, node: &Node, name: &str) {
+        if node.has_any(&self.keys.os) {
+            self.check_entries(node, name, &self.keys.os, Self::check_arch_resource);
+        } else {
+            self.check_resource(node, name);
+        }
+    }
+
+    fn check_os_command(&mut self, node: &Node, name: &str) {
+        match node.value {
+            NodeValue::Mapping(_) => {
+                self.check_entries(node, name, &self.keys.os, Self::check_string)
+            }
+            _ => self.check_string(node, name),
+        }
+    }
+
+    fn check_shell_command(&mut self, node: &Node, name: &str) {
+        match node.value {
+            NodeValue::Mapping(_) => {
+                self.check_entries(node, name, &self.keys.shell, Self::check_os_command)
+            }
+            _ => self.check_string(node, name),
+        }
+    }
+
+    fn check_src(&mut self, node: &Node, name: &str) {
+        match node.value {
+            NodeValue::Mapping(_) => {
+                self.check_entries(node, name, &self.keys.shell, Self::check_string_or_list)
+            }
+            _ => self.check_string_or_list(node, name),
+        }
+    }
+
+    fn check_load(&mut self, node: &Node, name: &str) {
+        match node.value {
+            NodeValue::Mapping(_) => {
+                self.check_entries(node, name, &self.keys.shell, Self::check_string)
+            }
+            _ => self.check_string(node, name),
+        }
+    }
+
+    fn check_exec(&mut self, node: &Node, name: &str) {
+        if let NodeValue::Scalar(..) = node.value {
+            return self.check_string(node, name);
+        }
+
+        if self.check_keys(node, name, &self.keys.exec).is_some() {
+            self.check_required(node, name, "run");
+            for key in ["run", "alias"] {
+                if let Some(value) = node.get(key) {
+                    self.check_string(value, &format!("{}.{}", name, key));
+                }
+            }
+            for key in ["use_symlink", "lazy"] {
+                if let Some(value) = node.get(key) {
+                    self.check_bool(value, &format!("{}.{}", name, key));
+                }
+            }
+        }
+    }
+
+    fn check_menu(&mut self, node: &Node, name: &str) {
+        if self.check_keys(node, name, &self.keys.menu).is_some() {
+            self.check_required(node, name, "menu_name");
+        }
+    }
+
+    fn check_condition(&mut self, node: &Node, name: &str) {
+        if self.check_keys(node, name, &self.keys.condition).is_none() {
+            return;
+        }
+
+        for key in ["os", "arch", "hostname", "shell", "command_exists"] {
+            if let Some(value) = node.get(key) {
+                self.check_string_or_list(value, &format!("{}.{}", name, key));
+            }
+        }
+        if let Some(NodeValue::Sequence(_) | NodeValue::Scalar(..)) =
+            node.get("env").map(|env| &env.value)
+        {
+            let env = node.get("env").unwrap();
+            self.report(
+                env,
+                format!("`{}.env` should be a map of variable names to values", name),
+            );
+        }
+    }
+
+    fn check_payload(&mut self, node: &Node, ids: &mut HashMap<String, (bool, String)>) {
+        self.payload_id = node.get("id").and_then(Node::as_str).map(str::to_owned);
+
+        let entries = match &node.value {
+            NodeValue::Mapping(entries) => entries,
+            _ => return self.report(node, "payload should be a map".to_string()),
+        };
+
+        for (key, _) in entries {
+            let key_name = key.as_str().unwrap_or_default();
+            if is_known(key_name, &self.keys.payload) {
+                continue;
+            }
+
+            // repo options misplaced at payload level
+            if is_known(key_name, &self.keys.repo) {
+                self.report(
+                    key,
+                    format!("`{}` belongs under `resource`, along with `repo`", key_name),
+                );
+            } else {
+                self.report_unknown_key(key, "payload", &self.keys.payload);
+            }
+        }
+
+        self.check_required(node, "payload", "id");
+        self.check_required(node, "payload", "resource");
+
+        if let (Some(id_node), Some(id)) = (node.get("id"), self.payload_id.to_owned()) {
+            let is_override = node.get("override").and_then(Node::as_str) == Some("true");
+            let location = format!("{}:{}", self.file.display(), id_node.line);
+            match ids.get(&id) {
+                Some((is_other_override, other_location)) if is_override == *is_other_override => {
+                    let other_location = other_location.to_owned();
+                    self.report(
+                        id_node,
+                        format!(
+                            "duplicate id, also defined at {}; mark exactly one of them with `override: true`",
+                            other_location
+                        ),
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    ids.insert(id, (is_override, location));
+                }
+            }
+        }
+
+        for (key, value) in entries {
+            let key_name = match key.as_str() {
+                Some(key_name) => key_name,
+                None => continue,
+            };
+            if value.is_null() {
+                continue;
+            }
+
+            match key_name {
+                "id" | "extract" => self.check_string(value, key_name),
+                "resource" => self.check_adaptive_resource(value, key_name),
+                "init" | "install" | "update" => self.check_shell_command(value, key_name),
+                "src" => self.check_src(value, key_name),
+                "load" => self.check_load(value, key_name),
+                "strip_components" => self.check_count(value, key_name),
+                "defer" | "override" => self.check_bool(value, key_name),
+                "trigger" | "depends_on" => self.check_string_or_list(value, key_name),
+                "when" => self.check_condition(value, key_name),
+                "exec" => self.check_exec(value, key_name),
+                "menu" => self.check_menu(value, key_name),
+                _ => {}
+            }
+        }
+
+        self.payload_id = None;
+    }
+
+    fn check_payloads(&mut self, node: &Node, ids: &mut HashMap<String, (bool, String)>) {
+        match &node.value {
+            NodeValue::Sequence(payloads) => {
+                for payload in payloads {
+                    self.check_payload(payload, ids);
+                }
+            }
+            _ => self.report(node, "payloads should be a list".to_string()),
+        }
+    }
+
+    fn check_document(&mut self, root: &Node, ids: &mut HashMap<String, (bool, String)>) {
+        match &root.value {
+            NodeValue::Sequence(_) => self.check_payloads(root, ids),
+            NodeValue::Mapping(_) => {
+                if self
+                    .check_keys(root, "config", &self.keys.top_level)
+                    .is_none()
+                {
+                    return;
+                }
+
+                if let Some(include) = root.get("include") {
+                    self.check_string_or_list(include, "include");
+                    let config_dir = get_dir(self.file).unwrap_or_default();
+                    let patterns = match &include.value {
+                        NodeValue::Sequence(nodes) => nodes.iter().collect(),
+                        _ => vec![include],
+                    };
+                    for pattern in patterns {
+                        match pattern.as_str().map(|p| resolve_include(&config_dir, p)) {
+                            Some(Ok(paths)) => {
+                                for path in paths {
+                                    if path.exists() {
+                                        self.includes.push(path);
+                                    } else {
+                                        let message =
+                                            format!("included file {} not found", path.display());
+                                        self.report(pattern, message);
+                                    }
+                                }
+                            }
+                            Some(Err(err)) => {
+                                self.report(pattern, format!("invalid include: {}", err))
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                if let Some(vars) = root.get("vars") {
+                    self.check_entries_any(vars, "vars");
+                }
+                if let Some(channel) = root.get("channel") {
+                    self.check_string(channel, "channel");
+                }
+                if let Some(libc) = root.get("libc") {
+                    self.check_libc(libc, "libc");
+                }
+                if let Some(payloads) = root.get("payloads") {
+                    self.check_payloads(payloads, ids);
+                }
+            }
+            _ if root.is_null() => {}
+            _ => self.report(
+                root,
+                "config should be a list of payloads or a map".to_string(),
+            ),
+        }
+    }
+
+    // map of arbitrary names to strings
+    fn check_entries_any(&mut self, node: &Node, name: &str) {
+        match &node.value {
+            NodeValue::Mapping(entries) => {
+                for (key, value) in entries {
+                    let key_name = key.as_str().unwrap_or_default();
+                    self.check_string(value, &format!("{}.{}", name, key_name));
+                }
+            }
+            _ => self.report(node, format!("`{}` should be a map", name)),
+        }
+    }
+}
+
+// diagnostics for a single config file, along with the files it includes
+fn check_file(
+    file: &Path,
+    keys: &ConfigKeys,
+    content: &str,
+    ids: &mut HashMap<String, (bool, String)>,
+) -> (Vec<Diagnostic>, Vec<PathBuf>) {
+    let mut builder = TreeBuilder::default();
+    if let Err(err) = Parser::new_from_str(content).load(&mut builder, false) {
+        let diagnostic = Diagnostic {
+            file: file.to_path_buf(),
+            position: Some((err.marker().line(), err.marker().col() + 1)),
+            payload_id: None,
+            message: format!("invalid yaml: {}", err.info()),
+        };
+        return (vec![diagnostic], vec![]);
+    }
+
+    let mut checker = Checker {
+        file,
+        keys,
+        payload_id: None,
+        diagnostics: vec![],
+        includes: vec![],
+    };
+    if let Some(root) = &builder.root {
+        checker.check_document(root, ids);
+    }
+
+    (checker.diagnostics, checker.includes)
+}
+
+// check the config file, its includes and conf.d, in the order they are loaded
+pub fn check_config_files(config_path: &Path, conf_d_path: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut ids = HashMap::new();
+    let keys = get_config_keys();
+    let mut checked_files = vec![];
+    let mut pending_files = vec![config_path.to_path_buf()];
+    pending_files.extend(get_conf_d_files(conf_d_path).unwrap_or_default());
+    pending_files.reverse();
+
+    while let Some(file) = pending_files.pop() {
+        let file = fs::canonicalize(&file).unwrap_or(file);
+        if checked_files.contains(&file) {
+            continue;
+        }
+        checked_files.push(file.to_owned());
+
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) => {
+                diagnostics.push(Diagnostic {
+                    file: file.to_owned(),
+                    position: None,
+                    payload_id: None,
+                    message: format!("unable to read config: {}", err),
+                });
+                continue;
+            }
+        };
+
+        let (file_diagnostics, includes) = check_file(&file, &keys, &content, &mut ids);
+        diagnostics.extend(file_diagnostics);
+        // includes are checked right after the including file
+        pending_files.extend(includes.into_iter().rev());
+    }
+
+    // anything the checks above missed surfaces when loading the config for real
+    if diagnostics.is_empty() {
+        if let Err(err) = load_config(config_path, conf_d_path) {
+            diagnostics.push(Diagnostic {
+                file: config_path.to_path_buf(),
+                position: None,
+                payload_id: None,
+                message: err.to_string(),
+            });
+        }
+    }
+
+    diagnostics
+}
+
+pub fn check_config() -> Result<Vec<Diagnostic>, Box<dyn std::error::Error>> {
+    Ok(check_config_files(
+        &get_config_path()?,
+        &get_conf_d_dir_path()?,
+    ))
+}
+
+#[cfg(test)]
+mod check_file_tests {
+    use super::*;
+
+    fn check(content: &str) -> Vec<String> {
+        let keys = get_config_keys();
+        check_file(Path::new("config.yml"), &keys, content, &mut HashMap::new())
+            .0
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_accept_valid_config() {
+        let config = r#"
+- id: fd
+  resource:
+    repo: sharkdp/fd
+    from_release: true
+    binary_pattern: "fd-.*-x86_64-unknown-linux-gnu.tar.gz"
+  exec: "**/fd"
+"#;
+
+        assert_eq!(check(config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn it_should_suggest_misspelt_keys() {
+        let config = r#"
+- id: fd
+  resource:
+    repo: sharkdp/fd
+    binary_patern: "fd-.*"
+"#;
+
+        assert_eq!(
+            check(config),
+            vec!["config.yml:5:5: payload `fd`: unknown key `binary_patern` in `resource`, did you mean `binary_pattern`?"]
+        );
+    }
+
+    #[test]
+    fn it_should_report_invalid_config() {
+        let config = r#"
+- id: fd
+  resource: https://example.com/fd.tar.gz
+  from_release: true
+- id: rg
+  resource:
+    url: https://example.com/rg.tar.gz
+    from_release: true
+- id: fd
+  resource:
+    repo: sharkdp/fd
+    binary_pattern: "fd-(x86"
+"#;
+
+        assert_eq!(
+            check(config),
+            vec![
+                "config.yml:4:3: payload `fd`: `from_release` belongs under `resource`, along with `repo`",
+                "config.yml:8:5: payload `rg`: `from_release` only applies to repo resources, not to `url` ones",
+                "config.yml:9:7: payload `fd`: duplicate id, also defined at config.yml:2; mark exactly one of them with `override: true`",
+                "config.yml:12:21: payload `fd`: invalid regex in `resource.binary_pattern`: regex parse error:\n    fd-(x86\n       ^\nerror: unclosed group",
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use sha2::{Digest, Sha256, Sha512};
+
+#[derive(Debug, PartialEq)]
+pub enum Checksum {
+    Sha256(String),
+    Sha512(String),
+}
+
+impl Checksum {
+    // accepts `sha256:<hex>`, `sha512:<hex>` or a bare hex digest
+    pub fn parse(checksum: &str) -> Result<Checksum, Box<dyn std::error::Error>> {
+        let (algorithm, digest) = match checksum.trim().split_once(':') {
+            Some((algorithm, digest)) => (Some(algorithm.to_lowercase()), digest.trim()),
+            None => (None, checksum.trim()),
+        };
+
+        if !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid checksum digest: {}", digest).into());
+        }
+
+        let digest = digest.to_lowercase();
+        match (algorithm.as_deref(), digest.len()) {
+            (Some("sha256"), 64) | (None, 64) => Ok(Checksum::Sha256(digest)),
+            (Some("sha512"), 128) | (None, 128) => Ok(Checksum::Sha512(digest)),
+            _ => Err(format!("unsupported checksum: {}", checksum).into()),
+        }
+    }
+
+    pub fn digest(&self) -> &str {
+        match self {
+            Checksum::Sha256(digest) => digest,
+            Checksum::Sha512(digest) => digest,
+        }
+    }
+}
+
+fn get_file_digest<D: Digest + io::Write>(
+    file_path: &Path,
+    mut hasher: D,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+pub fn get_file_sha256(file_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    get_file_digest(file_path, Sha256::new())
+}
+
+pub fn get_file_sha512(file_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    get_file_digest(file_path, Sha512::new())
+}
+
+pub fn verify_file_checksum(
+    file_path: &Path,
+    expected: &Checksum,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let actual = match expected {
+        Checksum::Sha256(_) => get_file_sha256(file_path)?,
+        Checksum::Sha512(_) => get_file_sha512(file_path)?,
+    };
+
+    if actual != expected.digest() {
+        return Err(format!(
+            "checksum mismatch for {}: expected {}, got {}",
+            file_path.display(),
+            expected.digest(),
+            actual
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+// find the checksum of a file in either a single-digest file (`<asset>.sha256`)
+// or a `<digest>  <file name>` listing (`SHA256SUMS`)
+pub fn find_checksum(content: &str, file_name: &str) -> Option<Checksum> {
+    let lines = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>();
+
+    let matched_line = lines.iter().find(|line| {
+        line.split_whitespace()
+            .nth(1)
+            .map(|name| name.trim_start_matches('*').trim_start_matches("./"))
+            == Some(file_name)
+    });
+
+    match (matched_line, lines.as_slice()) {
+        (Some(line), _) => Checksum::parse(line.split_whitespace().next()?).ok(),
+        (None, [line]) if line.split_whitespace().count() == 1 => Checksum::parse(line).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod checksum_tests {
+    use super::*;
+
+    const SHA256: &str = "b3a3b2a2ea2c4b9de6e1b1c1d4d0a5e5f4b7e2d6a0d1c9c8b7a6f5e4d3c2b1a0";
+
+    #[test]
+    fn it_should_parse_prefixed_and_bare_digests() {
+        let sha512 = "a".repeat(128);
+
+        assert_eq!(
+            Checksum::parse(&format!("sha256:{}", SHA256)).unwrap(),
+            Checksum::Sha256(SHA256.to_string())
+        );
+        assert_eq!(
+            Checksum::parse(&SHA256.to_uppercase()).unwrap(),
+            Checksum::Sha256(SHA256.to_string())
+        );
+        assert_eq!(
+            Checksum::parse(&sha512).unwrap(),
+            Checksum::Sha512(sha512.to_string())
+        );
+        assert!(Checksum::parse(&format!("sha512:{}", SHA256)).is_err());
+        assert!(Checksum::parse("md5:d41d8cd98f00b204e9800998ecf8427e").is_err());
+    }
+
+    #[test]
+    fn it_should_find_checksum_in_sums_file() {
+        let content = format!(
+            "{}  ripgrep-14.1.0-aarch64-apple-darwin.tar.gz\n{} *ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz\n",
+            "0".repeat(64),
+            SHA256
+        );
+
+        assert_eq!(
+            find_checksum(&content, "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz"),
+            Some(Checksum::Sha256(SHA256.to_string()))
+        );
+        assert_eq!(find_checksum(&content, "ripgrep.deb"), None);
+    }
+
+    #[test]
+    fn it_should_find_checksum_in_single_digest_file() {
+        assert_eq!(
+            find_checksum(&format!("{}\n", SHA256), "nvim-linux64.tar.gz"),
+            Some(Checksum::Sha256(SHA256.to_string()))
+        );
+    }
+}
@@ -3,6 +3,9 @@ use clap::{Parser, Subcommand};
 #[derive(Parser)]
 #[command(version)]
 pub struct Cli {
+    /// Log how releases and assets are picked
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -13,15 +16,71 @@ pub enum Commands {
     Init {
         /// Name of shell (Options: Zsh, Bash, PowerShell, etc.)
         shell: String,
+        /// Only install payloads pinned in orbiter.lock
+        #[arg(long)]
+        locked: bool,
+        /// Reinstall payloads whose config changed since they were installed
+        #[arg(long)]
+        apply_drift: bool,
+        /// Number of payloads to install concurrently (default: number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Render the init script afresh instead of sourcing the cached one
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Install payloads without initialising a shell
+    Install {
+        /// IDs of the payloads to install (default: all)
+        ids: Vec<String>,
+        /// Name of shell to run the install hooks with (default: $SHELL)
+        #[arg(long)]
+        shell: Option<String>,
+        /// Only install payloads pinned in orbiter.lock
+        #[arg(long)]
+        locked: bool,
+        /// Reinstall payloads whose config changed since they were installed
+        #[arg(long)]
+        apply_drift: bool,
+        /// Number of payloads to install concurrently (default: number of CPUs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Uninstall a payload, removing its files and shims
+    Uninstall {
+        /// ID of the payload to uninstall
+        id: String,
     },
     /// Update a payload
     Update {
         /// ID of the payload to update
         id: Option<String>,
     },
+    /// Show the release and assets a payload resolves to on this host
+    Resolve {
+        /// ID of the payload to resolve
+        id: String,
+    },
+    /// Validate the config, exiting non-zero on problems
+    Check,
+    /// Print the JSON Schema of the config file
+    Schema,
     /// List configured payloads
     List {
         /// Scope of the payloads to list (effective(default)/all)
         scope: Option<String>,
     },
+    /// Inspect or clear the download cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// List cached downloads, most recently used first
+    List,
+    /// Remove all cached downloads
+    Clean,
 }
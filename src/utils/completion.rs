@@ -1,6 +1,7 @@
 use super::shells::SupportedShell;
 
-pub fn load_completion(current_shell: &SupportedShell) {
+// shell code enabling completion for shells that require it
+pub fn get_completion(current_shell: &SupportedShell) -> String {
     let shell_specific_evaluatable = match current_shell {
         SupportedShell::Sh => "",
         SupportedShell::Bash => "",
@@ -10,5 +11,5 @@ pub fn load_completion(current_shell: &SupportedShell) {
         SupportedShell::WinCmd => "",
     };
 
-    println!("{}", shell_specific_evaluatable);
+    format!("{}\n", shell_specific_evaluatable)
 }
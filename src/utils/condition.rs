@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::env;
+
+use super::config::{Condition, ConditionValues, Payload};
+use super::platform::{get_arch, get_os, normalize_arch, normalize_os};
+use super::shells::SupportedShell;
+
+fn get_values(values: &ConditionValues) -> Vec<&str> {
+    match values {
+        ConditionValues::Single(value) => vec![value.as_str()],
+        ConditionValues::Multiple(values) => values.iter().map(String::as_str).collect(),
+    }
+}
+
+fn is_hostname_matched(pattern: &str, hostname: &str) -> bool {
+    match glob::Pattern::new(pattern) {
+        Ok(pattern) => pattern.matches(hostname),
+        Err(_) => pattern == hostname,
+    }
+}
+
+// first condition not holding on this host, if any
+fn get_unmet_condition(condition: &Condition, current_shell: &SupportedShell) -> Option<String> {
+    if let Some(os) = &condition.os {
+        let os = get_values(os);
+        if !os.iter().any(|os| normalize_os(os) == get_os()) {
+            return Some(format!("os is not {}", os.join(" or ")));
+        }
+    }
+
+    if let Some(arch) = &condition.arch {
+        let arch = get_values(arch);
+        if !arch.iter().any(|arch| normalize_arch(arch) == get_arch()) {
+            return Some(format!("arch is not {}", arch.join(" or ")));
+        }
+    }
+
+    if let Some(hostname) = &condition.hostname {
+        let patterns = get_values(hostname);
+        let current_hostname = hostname::get()
+            .map(|hostname| hostname.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !patterns
+            .iter()
+            .any(|pattern| is_hostname_matched(pattern, &current_hostname))
+        {
+            return Some(format!("hostname does not match {}", patterns.join(" or ")));
+        }
+    }
+
+    if let Some(vars) = &condition.env {
+        for (key, expected) in vars {
+            match (env::var(key), expected) {
+                (Err(_), _) => return Some(format!("env {} is not set", key)),
+                (Ok(value), Some(expected)) if &value != expected => {
+                    return Some(format!("env {} is not {}", key, expected))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(shell) = &condition.shell {
+        let shells = get_values(shell);
+        let current_shell_name = current_shell.as_program_str().trim_end_matches(".exe");
+        if !shells
+            .iter()
+            .any(|shell| shell.to_lowercase() == current_shell_name)
+        {
+            return Some(format!("shell is not {}", shells.join(" or ")));
+        }
+    }
+
+    if let Some(commands) = &condition.command_exists {
+        for command in get_values(commands) {
+            if which::which(command).is_err() {
+                return Some(format!("command {} is not found", command));
+            }
+        }
+    }
+
+    None
+}
+
+// why the payload is skipped on this host, if it is
+pub fn get_skip_reason(payload: &Payload, current_shell: &SupportedShell) -> Option<String> {
+    payload
+        .when
+        .as_ref()
+        .and_then(|condition| get_unmet_condition(condition, current_shell))
+}
+
+// why each skipped payload is skipped, including payloads depending on skipped ones
+pub fn get_skip_reasons(
+    payloads: &[Payload],
+    current_shell: &SupportedShell,
+) -> HashMap<String, String> {
+    let mut skip_reasons = payloads
+        .iter()
+        .filter_map(|payload| {
+            get_skip_reason(payload, current_shell).map(|reason| (payload.id.to_owned(), reason))
+        })
+        .collect::<HashMap<String, String>>();
+
+    loop {
+        let dependents = payloads
+            .iter()
+            .filter(|payload| !skip_reasons.contains_key(&payload.id))
+            .filter_map(|payload| {
+                payload
+                    .depends_on
+                    .iter()
+                    .flatten()
+                    .find(|id| skip_reasons.contains_key(*id))
+                    .map(|id| {
+                        (
+                            payload.id.to_owned(),
+                            format!("dependency {} is skipped", id),
+                        )
+                    })
+            })
+            .collect::<Vec<(String, String)>>();
+
+        if dependents.is_empty() {
+            return skip_reasons;
+        }
+
+        skip_reasons.extend(dependents);
+    }
+}
+
+#[cfg(test)]
+mod skip_reason_tests {
+    use super::*;
+
+    fn get_payload(when: &str) -> Payload {
+        serde_yaml::from_str(&format!(
+            "{{ id: fd, resource: https://example.com/fd.tar.gz, when: {} }}",
+            when
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_enable_matching_payloads() {
+        let payload = get_payload(&format!(
+            "{{ os: [{}], arch: {}, shell: zsh, hostname: '*' }}",
+            get_os(),
+            get_arch()
+        ));
+
+        assert_eq!(get_skip_reason(&payload, &SupportedShell::Zsh), None);
+    }
+
+    #[test]
+    fn it_should_skip_on_unmet_conditions() {
+        assert_eq!(
+            get_skip_reason(
+                &get_payload("{ shell: [fish, bash] }"),
+                &SupportedShell::Zsh
+            ),
+            Some("shell is not fish or bash".to_string())
+        );
+        assert_eq!(
+            get_skip_reason(
+                &get_payload("{ env: { ORBITER_TEST_UNSET_VAR: } }"),
+                &SupportedShell::Zsh
+            ),
+            Some("env ORBITER_TEST_UNSET_VAR is not set".to_string())
+        );
+        assert_eq!(
+            get_skip_reason(
+                &get_payload("{ command_exists: orbiter-test-missing-cmd }"),
+                &SupportedShell::Zsh
+            ),
+            Some("command orbiter-test-missing-cmd is not found".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_compare_env_values() {
+        let payload = get_payload("{ env: { ORBITER_TEST_CONDITION_VAR: work } }");
+
+        env::set_var("ORBITER_TEST_CONDITION_VAR", "work");
+        assert_eq!(get_skip_reason(&payload, &SupportedShell::Zsh), None);
+        env::set_var("ORBITER_TEST_CONDITION_VAR", "home");
+        assert_eq!(
+            get_skip_reason(&payload, &SupportedShell::Zsh),
+            Some("env ORBITER_TEST_CONDITION_VAR is not work".to_string())
+        );
+        env::remove_var("ORBITER_TEST_CONDITION_VAR");
+    }
+
+    #[test]
+    fn it_should_skip_dependents_of_skipped_payloads() {
+        let payloads = serde_yaml::from_str::<Vec<Payload>>(
+            r#"
+- id: a
+  resource: org/a
+  when: { shell: fish }
+- id: b
+  resource: org/b
+  depends_on: [a]
+- id: c
+  resource: org/c
+  depends_on: [b]
+- id: d
+  resource: org/d
+"#,
+        )
+        .unwrap();
+
+        let skip_reasons = get_skip_reasons(&payloads, &SupportedShell::Zsh);
+
+        assert_eq!(
+            skip_reasons,
+            HashMap::from([
+                ("a".to_string(), "shell is not fish".to_string()),
+                ("b".to_string(), "dependency a is skipped".to_string()),
+                ("c".to_string(), "dependency b is skipped".to_string()),
+            ])
+        );
+    }
+}
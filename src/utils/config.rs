@@ -1,83 +1,487 @@
+#![allow(clippy::large_enum_variant)]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::providers::Providers;
 
 use super::paths::*;
+use super::template::interpolate_payload;
+
+// a config file is either a list of payloads, or a map of includes, vars and payloads
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct ConfigDocument {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    vars: BTreeMap<String, String>,
+    channel: Option<String>, // default release channel of repos
+    libc: Option<Libc>,      // default libc preference of repos
+    #[serde(default)]
+    payloads: Vec<Payload>,
+}
+
+// schema of a config file, as either of its forms
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum ConfigFile {
+    Payloads(Vec<Payload>),
+    Document(ConfigDocument),
+}
+
+// flag unknown keys, as orbiter check does, so editors can point out typos
+fn deny_unknown_properties(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            if map.contains_key("properties") && !map.contains_key("additionalProperties") {
+                map.insert("additionalProperties".to_owned(), false.into());
+            }
+            map.values_mut().for_each(deny_unknown_properties);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(deny_unknown_properties),
+        _ => {}
+    }
+}
+
+// JSON Schema of the config file, generated from the config types
+pub fn get_config_schema() -> Result<String, Box<dyn std::error::Error>> {
+    let mut schema = serde_json::to_value(schemars::schema_for!(ConfigFile))?;
+    schema["title"] = "orbiter config".into();
+    deny_unknown_properties(&mut schema);
+
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+// keys of each config type and values of its enums, read from the schema so that
+// orbiter check follows the config types as fields are added
+#[derive(Debug, Default)]
+pub struct ConfigKeys {
+    pub top_level: Vec<String>,
+    pub payload: Vec<String>,
+    pub repo: Vec<String>,
+    pub verified_location: Vec<String>,
+    pub signature: Vec<String>,
+    pub os: Vec<String>,
+    pub arch: Vec<String>,
+    pub shell: Vec<String>,
+    pub exec: Vec<String>,
+    pub menu: Vec<String>,
+    pub condition: Vec<String>,
+    pub providers: Vec<String>,
+    pub libcs: Vec<String>,
+}
+
+// property names of a type, or of the map variant of an untagged enum
+fn get_schema_keys(defs: &serde_json::Value, type_name: &str) -> Vec<String> {
+    let def = &defs[type_name];
+    let properties = match def["anyOf"].as_array() {
+        Some(variants) => variants
+            .iter()
+            .find_map(|variant| variant["properties"].as_object()),
+        None => def["properties"].as_object(),
+    };
+
+    properties
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn get_schema_values(defs: &serde_json::Value, type_name: &str) -> Vec<String> {
+    defs[type_name]["enum"]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_owned))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn get_config_keys() -> ConfigKeys {
+    let schema = schemars::schema_for!(ConfigFile).to_value();
+    let defs = &schema["$defs"];
+
+    ConfigKeys {
+        top_level: get_schema_keys(defs, "ConfigDocument"),
+        payload: get_schema_keys(defs, "Payload"),
+        repo: get_schema_keys(defs, "Repo"),
+        verified_location: get_schema_keys(defs, "VerifiedLocation"),
+        signature: get_schema_keys(defs, "Signature"),
+        os: get_schema_keys(defs, "SupportedOSSpecificResource"),
+        arch: get_schema_keys(defs, "SupportedArchSpecificResource"),
+        shell: get_schema_keys(defs, "SupportedShellSpecificCommand"),
+        exec: get_schema_keys(defs, "Executable"),
+        menu: get_schema_keys(defs, "Menu"),
+        condition: get_schema_keys(defs, "Condition"),
+        providers: get_schema_values(defs, "Providers"),
+        libcs: get_schema_values(defs, "Libc"),
+    }
+}
+
+fn parse_config_document(
+    reader: &mut dyn Read,
+) -> Result<ConfigDocument, Box<dyn std::error::Error>> {
+    let value: serde_yaml::Value = serde_yaml::from_reader(reader)?;
+
+    Ok(match value {
+        serde_yaml::Value::Null => ConfigDocument::default(),
+        serde_yaml::Value::Sequence(_) => ConfigDocument {
+            include: vec![],
+            vars: BTreeMap::new(),
+            channel: None,
+            libc: None,
+            payloads: serde_yaml::from_value(value)?,
+        },
+        _ => serde_yaml::from_value(value)?,
+    })
+}
 
 pub fn from_reader(reader: &mut dyn Read) -> Result<Vec<Payload>, Box<dyn std::error::Error>> {
-    Ok(serde_yaml::from_reader(reader)?)
+    Ok(parse_config_document(reader)?.payloads)
+}
+
+// payloads merged from all config files, along with the files they were read from
+#[derive(Debug, Default)]
+pub struct LoadedConfig {
+    pub payloads: Vec<Payload>,
+    pub files: Vec<PathBuf>,
+    pub vars: BTreeMap<String, String>, // later files take precedence
+    pub channel: Option<String>,        // later files take precedence
+    pub libc: Option<Libc>,             // later files take precedence
+    sources: Vec<PathBuf>,              // file defining each payload
+}
+
+impl LoadedConfig {
+    fn merge_payload(
+        &mut self,
+        payload: Payload,
+        source: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let position = match self.payloads.iter().position(|p| p.id == payload.id) {
+            Some(position) => position,
+            None => {
+                self.payloads.push(payload);
+                self.sources.push(source.to_path_buf());
+                return Ok(());
+            }
+        };
+
+        match (
+            self.payloads[position].overrides.unwrap_or(false),
+            payload.overrides.unwrap_or(false),
+        ) {
+            (false, true) => {
+                self.payloads[position] = payload;
+                self.sources[position] = source.to_path_buf();
+            }
+            (true, false) => {}
+            _ => {
+                return Err(format!(
+                    "payload {} is defined in both {} and {}, mark exactly one of them with `override: true`",
+                    &payload.id,
+                    self.sources[position].display(),
+                    source.display()
+                )
+                .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_file(&mut self, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = fs::canonicalize(config_path)
+            .map_err(|err| format!("unable to read config {}: {}", config_path.display(), err))?;
+        // each file is only loaded once, which also breaks include cycles
+        if self.files.contains(&config_path) {
+            return Ok(());
+        }
+        self.files.push(config_path.to_owned());
+
+        let file = File::open(&config_path)?;
+        let document = parse_config_document(&mut BufReader::new(file))
+            .map_err(|err| format!("invalid config {}: {}", config_path.display(), err))?;
+
+        // included files come before the payloads of the including file
+        let config_dir = get_dir(&config_path)?;
+        for include in &document.include {
+            for included_path in resolve_include(&config_dir, include)? {
+                self.load_file(&included_path)?;
+            }
+        }
+
+        self.vars.extend(document.vars);
+        if document.channel.is_some() {
+            self.channel = document.channel;
+        }
+        if document.libc.is_some() {
+            self.libc = document.libc;
+        }
+        for payload in document.payloads {
+            self.merge_payload(payload, &config_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+// included file paths, relative to the including file, with globs sorted by path
+pub fn resolve_include(
+    config_dir: &Path,
+    include: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let include_path = match include.strip_prefix("~/") {
+        Some(path) => dirs::home_dir()
+            .ok_or("unable to locate home dir")?
+            .join(path),
+        None => config_dir.join(include),
+    };
+
+    if !include.contains(['*', '?', '[']) {
+        return Ok(vec![include_path]);
+    }
+
+    let include_pattern = if Path::new(include).is_absolute() || include.starts_with("~/") {
+        include_path.display().to_string()
+    } else {
+        Path::new(&glob::Pattern::escape(&config_dir.display().to_string()))
+            .join(include)
+            .display()
+            .to_string()
+    };
+    let mut included_paths = glob::glob(&include_pattern)?
+        .flatten()
+        .collect::<Vec<PathBuf>>();
+    included_paths.sort();
+
+    Ok(included_paths)
+}
+
+// conf.d/*.yml in file name order
+pub fn get_conf_d_files(conf_d_path: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if !conf_d_path.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut conf_d_paths = fs::read_dir(conf_d_path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("yml") | Some("yaml")
+                )
+        })
+        .collect::<Vec<PathBuf>>();
+    conf_d_paths.sort();
+
+    Ok(conf_d_paths)
+}
+
+// the config file, its includes, then conf.d/*.yml in file name order
+pub fn load_config(
+    config_path: &Path,
+    conf_d_path: &Path,
+) -> Result<LoadedConfig, Box<dyn std::error::Error>> {
+    let mut config = LoadedConfig::default();
+    config.load_file(config_path)?;
+
+    for path in get_conf_d_files(conf_d_path)? {
+        config.load_file(&path)?;
+    }
+
+    // interpolate once all vars are known
+    let payloads = std::mem::take(&mut config.payloads);
+    let vars = &config.vars;
+    config.payloads = payloads
+        .into_iter()
+        .map(|payload| interpolate_payload(payload, vars))
+        .collect::<Result<Vec<Payload>, Box<dyn std::error::Error>>>()?;
+
+    // repo defaults
+    for payload in config.payloads.iter_mut() {
+        for repo in payload.resource.get_repos_mut() {
+            if repo.channel.is_none() {
+                repo.channel = config.channel.to_owned();
+            }
+            if repo.libc.is_none() {
+                repo.libc = config.libc.to_owned();
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+pub fn get_config() -> Result<LoadedConfig, Box<dyn std::error::Error>> {
+    load_config(&get_config_path()?, &get_conf_d_dir_path()?)
 }
 
 pub fn get_payloads() -> Result<Vec<Payload>, Box<dyn std::error::Error>> {
-    let config_path = get_config_path()?;
-    let file = File::open(config_path)?;
-    let mut reader = BufReader::new(file);
-    from_reader(&mut reader)
+    Ok(get_config()?.payloads)
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Repo {
     pub repo: String,
     pub provider: Option<Providers>,
+    pub host: Option<String>, // hostname or base url of a self-hosted instance
+    pub token_env: Option<String>, // env var holding the API token
     pub from_release: Option<bool>,
-    pub ver: Option<String>,
+    pub ver: Option<String>, // tag, branch, commit or semver requirement
+    pub tag_pattern: Option<String>, // regex selecting the tags ver applies to
+    pub channel: Option<String>, // stable, prerelease, nightly or a tag regex
+    pub libc: Option<Libc>,  // libc of linux assets to prefer
     pub binary_pattern: Option<String>,
+    pub checksum: Option<String>, // sha256:<hex> or sha512:<hex>
+    pub signature: Option<Signature>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Libc {
+    Gnu,
+    #[default]
+    Musl, // statically linked builds run on either
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl fmt::Display for Libc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Libc::Gnu => write!(f, "gnu"),
+            Libc::Musl => write!(f, "musl"),
+        }
+    }
+}
+
+// detached signature of a downloaded asset, checked against the given public key
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Signature {
+    pub minisign: Option<String>,
+    pub cosign: Option<String>,
+    pub url: Option<String>, // defaults to <asset url>.minisig or <asset url>.sig
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct VerifiedLocation {
+    pub url: String,
+    pub checksum: Option<String>,
+    pub signature: Option<Signature>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Resource {
     Location(String),
+    VerifiedLocation(VerifiedLocation),
     Repo(Repo),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum AdaptiveResource {
     Standard(Resource),
     OSSpecific(SupportedOSSpecificResource),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedOSSpecificResource {
     pub linux: Option<OSSpecificResource>,
     pub macos: Option<OSSpecificResource>,
     pub windows: Option<OSSpecificResource>,
+    pub freebsd: Option<OSSpecificResource>,
+    pub netbsd: Option<OSSpecificResource>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum OSSpecificResource {
     Standard(Resource),
     ArchSpecific(SupportedArchSpecificResource),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedArchSpecificResource {
     pub x86_64: Option<Resource>,
     pub aarch64: Option<Resource>,
+    pub i686: Option<Resource>,
+    pub armv7: Option<Resource>,
+    pub riscv64: Option<Resource>,
+    pub ppc64le: Option<Resource>,
+    pub s390x: Option<Resource>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl AdaptiveResource {
+    // repo resources of every os and arch
+    pub fn get_repos_mut(&mut self) -> Vec<&mut Repo> {
+        let resources = match self {
+            AdaptiveResource::Standard(resource) => vec![resource],
+            AdaptiveResource::OSSpecific(os_specific_resource) => vec![
+                &mut os_specific_resource.linux,
+                &mut os_specific_resource.macos,
+                &mut os_specific_resource.windows,
+                &mut os_specific_resource.freebsd,
+                &mut os_specific_resource.netbsd,
+            ]
+            .into_iter()
+            .flatten()
+            .flat_map(|resource| match resource {
+                OSSpecificResource::Standard(resource) => vec![resource],
+                OSSpecificResource::ArchSpecific(arch_specific_resource) => vec![
+                    &mut arch_specific_resource.x86_64,
+                    &mut arch_specific_resource.aarch64,
+                    &mut arch_specific_resource.i686,
+                    &mut arch_specific_resource.armv7,
+                    &mut arch_specific_resource.riscv64,
+                    &mut arch_specific_resource.ppc64le,
+                    &mut arch_specific_resource.s390x,
+                ]
+                .into_iter()
+                .flatten()
+                .collect(),
+            })
+            .collect(),
+        };
+
+        resources
+            .into_iter()
+            .filter_map(|resource| match resource {
+                Resource::Repo(repo) => Some(repo),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedOSSpecificCommand {
     pub linux: Option<String>,
     pub macos: Option<String>,
     pub windows: Option<String>,
+    pub freebsd: Option<String>,
+    pub netbsd: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum OSSpecificCommand {
     Generic(String),
     OSSpecific(SupportedOSSpecificCommand),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedShellSpecificCommand {
     pub sh: Option<OSSpecificCommand>,
     pub zsh: Option<OSSpecificCommand>,
@@ -87,14 +491,14 @@ pub struct SupportedShellSpecificCommand {
     pub wincmd: Option<OSSpecificCommand>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ShellSpecificCommand {
     Generic(String),
     ShellSpecific(SupportedShellSpecificCommand),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Executable {
     Run(String),
@@ -102,10 +506,11 @@ pub enum Executable {
         run: String,
         alias: Option<String>,
         use_symlink: Option<bool>,
+        lazy: Option<bool>, // install the payload on the first call of the shim
     },
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Menu {
     pub menu_name: String,
     pub name: Option<String>,
@@ -113,14 +518,14 @@ pub struct Menu {
     pub icon: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum SourceTarget {
     Single(String),
     Multiple(Vec<String>),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedShellSpecificSourceTarget {
     pub sh: Option<SourceTarget>,
     pub zsh: Option<SourceTarget>,
@@ -130,14 +535,14 @@ pub struct SupportedShellSpecificSourceTarget {
     pub wincmd: Option<SourceTarget>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ShellSpecificSourceTarget {
     Generic(SourceTarget),
     ShellSpecific(SupportedShellSpecificSourceTarget),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SupportedShellSpecificEvaluatable {
     pub sh: Option<String>,
     pub zsh: Option<String>,
@@ -147,14 +552,38 @@ pub struct SupportedShellSpecificEvaluatable {
     pub wincmd: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ShellSpecificEvaluatable {
     Generic(String),
     ShellSpecific(SupportedShellSpecificEvaluatable),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Trigger {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ConditionValues {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Condition {
+    pub os: Option<ConditionValues>,
+    pub arch: Option<ConditionValues>,
+    pub hostname: Option<ConditionValues>,             // globs
+    pub env: Option<BTreeMap<String, Option<String>>>, // set, or set to the given value
+    pub shell: Option<ConditionValues>,
+    pub command_exists: Option<ConditionValues>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Payload {
     // The `string_or_struct` function delegates deserialization to a type's
     // `FromStr` impl if given a string, and to the type's `Deserialize` impl if
@@ -165,10 +594,17 @@ pub struct Payload {
     pub init: Option<ShellSpecificCommand>,
     pub resource: AdaptiveResource,
     pub extract: Option<String>, // path to the file to be extracted
+    pub strip_components: Option<usize>, // leading dirs to drop from archive entries
     pub install: Option<ShellSpecificCommand>,
     pub update: Option<ShellSpecificCommand>,
     pub src: Option<ShellSpecificSourceTarget>,
     pub load: Option<ShellSpecificEvaluatable>,
+    pub defer: Option<bool>,      // src and load after the first prompt
+    pub trigger: Option<Trigger>, // src and load on the first call of these commands
+    pub depends_on: Option<Vec<String>>, // ids of payloads to install, src and load first
+    pub when: Option<Condition>,  // skip the payload unless all conditions hold
+    #[serde(rename = "override")]
+    pub overrides: Option<bool>, // replace the payload with the same id from another config file
     pub exec: Option<Executable>,
     pub menu: Option<Menu>,
 }
@@ -201,7 +637,13 @@ mod parse_tests {
                 update: None,
                 src: None,
                 extract: None,
+                strip_components: None,
                 load: None,
+                defer: None,
+                trigger: None,
+                depends_on: None,
+                when: None,
+                overrides: None,
                 exec: Some( Executable::Run("**/firefox".to_string())),
                 menu: None
             }
@@ -235,7 +677,7 @@ mod parse_tests {
                 install: Some(ShellSpecificCommand::ShellSpecific(SupportedShellSpecificCommand{
                         sh:Some( OSSpecificCommand::OSSpecific( SupportedOSSpecificCommand{
                             macos: Some("./GitAhead*.sh --include-subdir".to_string()),
-                            linux: None, windows: None}
+                            linux: None, windows: None, freebsd: None, netbsd: None}
                         )),
                         zsh: None,
                         bash: None,
@@ -247,7 +689,13 @@ mod parse_tests {
                 update: None,
                 src: None,
                 extract: None,
+                strip_components: None,
                 load: None,
+                defer: None,
+                trigger: None,
+                depends_on: None,
+                when: None,
+                overrides: None,
                 exec: Some(Executable::Run("**/firefox".to_string())),
                 menu: Some(Menu {
                     menu_name: "Firefox".to_string(),
@@ -283,9 +731,16 @@ mod parse_tests {
             resource: AdaptiveResource::Standard(Resource::Repo(Repo {
                 repo: "gitahead/gitahead".to_string(),
                 provider: None,
+                host: None,
+                token_env: None,
                 ver: None,
+                tag_pattern: None,
+                channel: None,
+                libc: None,
                 from_release: None,
                 binary_pattern: None,
+                checksum: None,
+                signature: None,
             })),
             install: Some(ShellSpecificCommand::ShellSpecific(
                 SupportedShellSpecificCommand {
@@ -293,6 +748,8 @@ mod parse_tests {
                         macos: Some("./GitAhead*.sh --include-subdir".to_string()),
                         linux: None,
                         windows: None,
+                        freebsd: None,
+                        netbsd: None,
                     })),
                     zsh: None,
                     bash: None,
@@ -304,11 +761,18 @@ mod parse_tests {
             update: None,
             src: None,
             extract: None,
+            strip_components: None,
             load: None,
+            defer: None,
+            trigger: None,
+            depends_on: None,
+            when: None,
+            overrides: None,
             exec: Some(Executable::Command {
                 run: "**/GitAhead".to_string(),
                 alias: Some("gitahead".to_string()),
                 use_symlink: None,
+                lazy: None,
             }),
             menu: None,
         }];
@@ -331,18 +795,46 @@ mod parse_tests {
 
         let actual = parse(config).unwrap();
         let actual_resource = &actual.first().unwrap().resource;
-        if let AdaptiveResource::Standard(res) = actual_resource {
-            if let Resource::Repo(rel) = res {
-                assert_eq!(rel.provider.as_ref().unwrap(), &Providers::GitHub);
-                assert_eq!(rel.from_release.unwrap(), true);
-                assert_eq!(rel.binary_pattern.as_ref().unwrap(), "*.tar.gz");
-                return;
-            }
+        if let AdaptiveResource::Standard(Resource::Repo(rel)) = actual_resource {
+            assert_eq!(rel.provider.as_ref().unwrap(), &Providers::GitHub);
+            assert!(rel.from_release.unwrap());
+            assert_eq!(rel.binary_pattern.as_ref().unwrap(), "*.tar.gz");
+            return;
         }
 
         panic!("Invalid resource repo config")
     }
 
+    #[test]
+    fn it_should_parse_verified_location() {
+        let config = r#"
+        - id: kubectl
+          resource:
+            url: https://dl.k8s.io/release/v1.29.0/bin/linux/amd64/kubectl
+            checksum: sha256:e1c38137db8d8777eed8813646b59bf4d22d19b9011ab11dc28e2e34f6b80a05
+            signature:
+              cosign: ~/.config/kubernetes/cosign.pub
+          exec: kubectl
+        "#;
+
+        let actual = parse(config).unwrap();
+        let actual_resource = &actual.first().unwrap().resource;
+        let expected = Resource::VerifiedLocation(VerifiedLocation {
+            url: "https://dl.k8s.io/release/v1.29.0/bin/linux/amd64/kubectl".to_string(),
+            checksum: Some(
+                "sha256:e1c38137db8d8777eed8813646b59bf4d22d19b9011ab11dc28e2e34f6b80a05"
+                    .to_string(),
+            ),
+            signature: Some(Signature {
+                minisign: None,
+                cosign: Some("~/.config/kubernetes/cosign.pub".to_string()),
+                url: None,
+            }),
+        });
+
+        assert_eq!(actual_resource, &AdaptiveResource::Standard(expected))
+    }
+
     // #[test]
     // fn it_should_parse_repo() {
     //     let config = r#"
@@ -411,16 +903,14 @@ mod parse_tests {
             "https://storage.googleapis.com/minikube/releases/latest/minikube-darwin-arm64";
 
         if let AdaptiveResource::OSSpecific(os_specific) = actual_resource {
-            if let Some(macos_os_specific_resource) = &os_specific.macos {
-                if let OSSpecificResource::ArchSpecific(arch_specific_resource) =
-                    macos_os_specific_resource
-                {
-                    if let Some(aarch64_arch_specific_resource) = &arch_specific_resource.aarch64 {
-                        if let Resource::Location(location) = aarch64_arch_specific_resource {
-                            assert_eq!(location, expected)
-                        } else {
-                            panic!("No location")
-                        }
+            if let Some(OSSpecificResource::ArchSpecific(arch_specific_resource)) =
+                &os_specific.macos
+            {
+                if let Some(aarch64_arch_specific_resource) = &arch_specific_resource.aarch64 {
+                    if let Resource::Location(location) = aarch64_arch_specific_resource {
+                        assert_eq!(location, expected)
+                    } else {
+                        panic!("No location")
                     }
                 }
             }
@@ -458,9 +948,16 @@ mod from_reader_tests {
             resource: AdaptiveResource::Standard(Resource::Repo(Repo {
                 repo: "gitahead/gitahead".to_string(),
                 provider: None,
+                host: None,
+                token_env: None,
                 ver: None,
+                tag_pattern: None,
+                channel: None,
+                libc: None,
                 from_release: None,
                 binary_pattern: None,
+                checksum: None,
+                signature: None,
             })),
             install: Some(ShellSpecificCommand::ShellSpecific(
                 SupportedShellSpecificCommand {
@@ -468,6 +965,8 @@ mod from_reader_tests {
                         macos: Some("./GitAhead*.sh --include-subdir".to_string()),
                         linux: None,
                         windows: None,
+                        freebsd: None,
+                        netbsd: None,
                     })),
                     zsh: None,
                     bash: None,
@@ -479,11 +978,18 @@ mod from_reader_tests {
             update: None,
             src: None,
             extract: None,
+            strip_components: None,
             load: None,
+            defer: None,
+            trigger: None,
+            depends_on: None,
+            when: None,
+            overrides: None,
             exec: Some(Executable::Command {
                 run: "**/GitAhead".to_string(),
                 alias: Some("gitahead".to_string()),
                 use_symlink: None,
+                lazy: None,
             }),
             menu: None,
         }];
@@ -491,3 +997,146 @@ mod from_reader_tests {
         assert_eq!(actual, expected)
     }
 }
+
+#[cfg(test)]
+mod load_config_tests {
+    use super::*;
+
+    fn get_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "orbiter_load_config_tests_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(dir.join("base")).unwrap();
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+
+        dir
+    }
+
+    fn get_ids(config: &LoadedConfig) -> Vec<String> {
+        config.payloads.iter().map(|p| p.id.to_owned()).collect()
+    }
+
+    #[test]
+    fn it_should_merge_includes_and_conf_d() {
+        let dir = get_test_dir("merge");
+        fs::write(
+            dir.join("config.yml"),
+            "include: [base/*.yml]\npayloads:\n  - { id: user, resource: https://example.com/user }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("base/b.yml"),
+            "- { id: fzf, resource: https://example.com/fzf }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("base/a.yml"),
+            "- { id: fd, resource: https://example.com/fd }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("conf.d/10-fd.yml"),
+            "- { id: fd, resource: https://example.com/fd-fork, override: true }\n",
+        )
+        .unwrap();
+
+        let config = load_config(&dir.join("config.yml"), &dir.join("conf.d")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(get_ids(&config), vec!["fd", "fzf", "user"]);
+        assert_eq!(
+            config.payloads[0].resource,
+            AdaptiveResource::Standard(Resource::Location(
+                "https://example.com/fd-fork".to_string()
+            ))
+        );
+        assert_eq!(config.files.len(), 4);
+    }
+
+    #[test]
+    fn it_should_default_repo_channels() {
+        let dir = get_test_dir("channel");
+        fs::write(
+            dir.join("config.yml"),
+            r#"
+channel: nightly
+payloads:
+  - { id: nvim, resource: { repo: neovim/neovim, from_release: true } }
+  - id: fd
+    resource:
+      linux: { repo: sharkdp/fd, from_release: true, channel: stable }
+"#,
+        )
+        .unwrap();
+
+        let mut config = load_config(&dir.join("config.yml"), &dir.join("conf.d")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let channels = config
+            .payloads
+            .iter_mut()
+            .flat_map(|payload| payload.resource.get_repos_mut())
+            .map(|repo| repo.channel.to_owned().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(channels, vec!["nightly", "stable"]);
+    }
+
+    #[test]
+    fn it_should_reject_duplicate_ids() {
+        let dir = get_test_dir("duplicate");
+        fs::write(
+            dir.join("config.yml"),
+            "- { id: fd, resource: https://example.com/fd }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("conf.d/fd.yml"),
+            "- { id: fd, resource: https://example.com/fd-fork }\n",
+        )
+        .unwrap();
+
+        let err = load_config(&dir.join("config.yml"), &dir.join("conf.d")).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(err
+            .to_string()
+            .starts_with("payload fd is defined in both "));
+    }
+}
+
+#[cfg(test)]
+mod config_schema_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_generate_schema_from_config_types() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&get_config_schema().unwrap()).unwrap();
+        let payload = &schema["$defs"]["Payload"];
+
+        assert_eq!(payload["required"], serde_json::json!(["id", "resource"]));
+        assert_eq!(payload["additionalProperties"], false);
+        assert!(payload["properties"]["override"].is_object());
+    }
+}
+
+#[cfg(test)]
+mod config_keys_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_read_keys_from_config_types() {
+        let keys = get_config_keys();
+
+        assert!(keys.top_level.contains(&"payloads".to_string()));
+        assert!(keys.payload.contains(&"strip_components".to_string()));
+        assert!(keys.repo.contains(&"binary_pattern".to_string()));
+        assert!(keys.exec.contains(&"lazy".to_string()));
+        assert!(keys.os.contains(&"linux".to_string()));
+        assert!(keys.condition.contains(&"command_exists".to_string()));
+        assert_eq!(keys.libcs, vec!["gnu", "musl"]);
+        assert!(keys.providers.contains(&"github".to_string()));
+    }
+}
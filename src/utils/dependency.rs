@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+
+use super::config::Payload;
+
+fn get_payload<'a>(payloads: &[&'a Payload], id: &str) -> Option<&'a Payload> {
+    payloads.iter().find(|payload| payload.id == id).copied()
+}
+
+fn visit<'a>(
+    payloads: &[&'a Payload],
+    payload: &'a Payload,
+    visiting: &mut Vec<&'a str>,
+    visited: &mut HashSet<&'a str>,
+    ordered: &mut Vec<&'a Payload>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if visited.contains(payload.id.as_str()) {
+        return Ok(());
+    }
+
+    if let Some(position) = visiting.iter().position(|id| *id == payload.id) {
+        let mut cycle = visiting[position..].to_vec();
+        cycle.push(&payload.id);
+        return Err(format!("dependency cycle: {}", cycle.join(" -> ")).into());
+    }
+
+    visiting.push(&payload.id);
+    for dependency_id in payload.depends_on.iter().flatten() {
+        let dependency = get_payload(payloads, dependency_id).ok_or(format!(
+            "payload {} depends on {}, which is not configured",
+            &payload.id, dependency_id
+        ))?;
+        visit(payloads, dependency, visiting, visited, ordered)?;
+    }
+    visiting.pop();
+
+    visited.insert(&payload.id);
+    ordered.push(payload);
+
+    Ok(())
+}
+
+// the payloads with the given ids (default: all) along with their dependencies,
+// ordered so that dependencies come first and config order is kept otherwise
+pub fn resolve_payloads<'a>(
+    payloads: &[&'a Payload],
+    ids: &[String],
+) -> Result<Vec<&'a Payload>, Box<dyn std::error::Error>> {
+    let selected_payloads = if ids.is_empty() {
+        payloads.to_vec()
+    } else {
+        ids.iter()
+            .map(|id| get_payload(payloads, id).ok_or(format!("Payload with id {} not found", id)))
+            .collect::<Result<Vec<&Payload>, String>>()?
+    };
+
+    let mut visited = HashSet::new();
+    let mut ordered = vec![];
+    for payload in selected_payloads {
+        visit(payloads, payload, &mut vec![], &mut visited, &mut ordered)?;
+    }
+
+    Ok(ordered)
+}
+
+// why each payload cannot be ordered, for payloads in or depending on a cycle or on a
+// payload that is not configured
+pub fn get_unresolvable_payloads(payloads: &[&Payload]) -> HashMap<String, String> {
+    payloads
+        .iter()
+        .filter_map(|payload| {
+            visit(
+                payloads,
+                payload,
+                &mut vec![],
+                &mut HashSet::new(),
+                &mut vec![],
+            )
+            .err()
+            .map(|err| (payload.id.to_owned(), err.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod resolve_payloads_tests {
+    use super::*;
+
+    fn parse(config: &str) -> Vec<Payload> {
+        serde_yaml::from_str(config).unwrap()
+    }
+
+    fn resolve_all<'a>(
+        payloads: &'a [Payload],
+        ids: &[String],
+    ) -> Result<Vec<&'a Payload>, Box<dyn std::error::Error>> {
+        resolve_payloads(&payloads.iter().collect::<Vec<&Payload>>(), ids)
+    }
+
+    fn get_ids(payloads: &[&Payload]) -> Vec<String> {
+        payloads
+            .iter()
+            .map(|payload| payload.id.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_order_dependencies_first() {
+        let payloads = parse(
+            r#"
+            - { id: starship-init, resource: https://example.com/init.sh, depends_on: [starship] }
+            - { id: fzf, resource: https://example.com/fzf.tar.gz }
+            - { id: starship, resource: https://example.com/starship.tar.gz }
+            "#,
+        );
+
+        assert_eq!(
+            get_ids(&resolve_all(&payloads, &[]).unwrap()),
+            vec!["starship", "starship-init", "fzf"]
+        );
+        assert_eq!(
+            get_ids(&resolve_all(&payloads, &["starship-init".to_string()]).unwrap()),
+            vec!["starship", "starship-init"]
+        );
+    }
+
+    #[test]
+    fn it_should_name_the_cycle() {
+        let payloads = parse(
+            r#"
+            - { id: a, resource: https://example.com/a, depends_on: [b] }
+            - { id: b, resource: https://example.com/b, depends_on: [c] }
+            - { id: c, resource: https://example.com/c, depends_on: [a] }
+            "#,
+        );
+
+        assert_eq!(
+            resolve_all(&payloads, &[]).unwrap_err().to_string(),
+            "dependency cycle: a -> b -> c -> a"
+        );
+    }
+
+    #[test]
+    fn it_should_report_missing_dependencies() {
+        let payloads = parse(
+            r#"
+            - { id: a, resource: https://example.com/a, depends_on: [missing] }
+            "#,
+        );
+
+        assert_eq!(
+            resolve_all(&payloads, &[]).unwrap_err().to_string(),
+            "payload a depends on missing, which is not configured"
+        );
+    }
+
+    #[test]
+    fn it_should_list_unresolvable_payloads_with_their_dependents() {
+        let payloads = parse(
+            r#"
+            - { id: a, resource: https://example.com/a, depends_on: [missing] }
+            - { id: b, resource: https://example.com/b, depends_on: [a] }
+            - { id: c, resource: https://example.com/c, depends_on: [d] }
+            - { id: d, resource: https://example.com/d, depends_on: [c] }
+            - { id: e, resource: https://example.com/e }
+            "#,
+        );
+
+        assert_eq!(
+            get_unresolvable_payloads(&payloads.iter().collect::<Vec<&Payload>>()),
+            HashMap::from([
+                (
+                    "a".to_string(),
+                    "payload a depends on missing, which is not configured".to_string()
+                ),
+                (
+                    "b".to_string(),
+                    "payload a depends on missing, which is not configured".to_string()
+                ),
+                ("c".to_string(), "dependency cycle: c -> d -> c".to_string()),
+                ("d".to_string(), "dependency cycle: d -> c -> d".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::paths::get_download_cache_dir_path;
+
+pub const ORBITER_CACHE_MAX_SIZE_ENV_KEY: &str = "ORBITER_CACHE_MAX_SIZE";
+const DEFAULT_CACHE_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+const CACHE_ENTRIES_DIR: &str = "entries";
+const CACHE_BLOBS_DIR: &str = "blobs";
+// blobs younger than this may not have their entry saved yet by another orbiter
+const UNREFERENCED_BLOB_GRACE_SECS: u64 = 60;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// a downloaded url, its content stored once per sha256 under blobs/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub last_used: u64, // unix seconds, for LRU eviction
+}
+
+// hashes whatever is written through it
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn get_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+// unique within the cache dir, as payloads are installed concurrently
+fn get_temp_path(dir: &Path) -> PathBuf {
+    dir.join(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+// entries/<sha256 of url>.json
+fn get_entry_path(cache_dir: &Path, url: &str) -> PathBuf {
+    cache_dir.join(CACHE_ENTRIES_DIR).join(format!(
+        "{}.json",
+        hex::encode(Sha256::digest(url.as_bytes()))
+    ))
+}
+
+// blobs/<sha256 of content>
+fn get_blob_path(cache_dir: &Path, sha256: &str) -> PathBuf {
+    cache_dir.join(CACHE_BLOBS_DIR).join(sha256)
+}
+
+fn save_entry(cache_dir: &Path, entry: &CacheEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let entries_dir = cache_dir.join(CACHE_ENTRIES_DIR);
+    fs::create_dir_all(&entries_dir)?;
+
+    // written aside then renamed, so readers never see a partial entry
+    let temp_path = get_temp_path(&entries_dir);
+    let mut writer = BufWriter::new(File::create(&temp_path)?);
+    let written = serde_json::to_writer(&mut writer, entry)
+        .map_err(io::Error::from)
+        .and_then(|_| writer.flush());
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    fs::rename(&temp_path, get_entry_path(cache_dir, &entry.url))?;
+
+    Ok(())
+}
+
+fn read_entry(entry_path: &Path) -> Option<CacheEntry> {
+    let file = File::open(entry_path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+fn get_entry_in(cache_dir: &Path, url: &str) -> Option<CacheEntry> {
+    read_entry(&get_entry_path(cache_dir, url))
+        .filter(|entry| entry.url == url && get_blob_path(cache_dir, &entry.sha256).exists())
+}
+
+fn add_entry_in<R: Read>(
+    cache_dir: &Path,
+    url: &str,
+    file_name: &str,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    reader: &mut R,
+) -> Result<CacheEntry, Box<dyn std::error::Error>> {
+    let blobs_dir = cache_dir.join(CACHE_BLOBS_DIR);
+    fs::create_dir_all(&blobs_dir)?;
+
+    // hash while downloading, then move into place under the content hash
+    let temp_path = get_temp_path(&blobs_dir);
+    let mut writer = HashingWriter {
+        inner: BufWriter::new(File::create(&temp_path)?),
+        hasher: Sha256::new(),
+        size: 0,
+    };
+    if let Err(e) = io::copy(reader, &mut writer).and_then(|_| writer.flush()) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    let sha256 = hex::encode(writer.hasher.finalize());
+    fs::rename(&temp_path, get_blob_path(cache_dir, &sha256))?;
+
+    let entry = CacheEntry {
+        url: url.to_owned(),
+        file_name: file_name.to_owned(),
+        sha256,
+        size: writer.size,
+        etag,
+        last_modified,
+        last_used: get_now(),
+    };
+    save_entry(cache_dir, &entry)?;
+
+    Ok(entry)
+}
+
+fn list_entries_in(cache_dir: &Path) -> Result<Vec<CacheEntry>, Box<dyn std::error::Error>> {
+    let entries_dir = cache_dir.join(CACHE_ENTRIES_DIR);
+    if !entries_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = vec![];
+    for dir_entry in fs::read_dir(entries_dir)? {
+        let path = dir_entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(entry) = read_entry(&path) {
+                entries.push(entry);
+            }
+        }
+    }
+    // most recently used first
+    entries.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.url.cmp(&b.url)));
+
+    Ok(entries)
+}
+
+// drops least recently used entries until the blobs they share fit in max_size
+fn evict_in(cache_dir: &Path, max_size: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let mut kept_blobs = HashSet::new();
+    let mut evicted_blobs = HashSet::new();
+    let mut total_size = 0;
+    for entry in list_entries_in(cache_dir)? {
+        let is_kept = get_blob_path(cache_dir, &entry.sha256).exists()
+            && (kept_blobs.contains(&entry.sha256) || total_size + entry.size <= max_size);
+        if is_kept {
+            if kept_blobs.insert(entry.sha256.to_owned()) {
+                total_size += entry.size;
+            }
+        } else {
+            remove_if_exists(&get_entry_path(cache_dir, &entry.url))?;
+            evicted_blobs.insert(entry.sha256);
+        }
+    }
+
+    // blobs no longer referenced by any entry, leaving in-flight downloads and blobs
+    // another orbiter has yet to save an entry for alone
+    let blobs_dir = cache_dir.join(CACHE_BLOBS_DIR);
+    if blobs_dir.exists() {
+        for dir_entry in fs::read_dir(blobs_dir)? {
+            let path = dir_entry?.path();
+            let is_removed = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    !name.starts_with('.')
+                        && !kept_blobs.contains(name)
+                        && (evicted_blobs.contains(name) || !is_recently_modified(&path))
+                });
+            if is_removed {
+                remove_if_exists(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_recently_modified(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age.as_secs() < UNREFERENCED_BLOB_GRACE_SECS)
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// sizes like 500M or 2G, in powers of 1024
+pub fn parse_size(size: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let size = size.trim().to_uppercase();
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+    let multiplier: u64 = match unit.trim().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid size: {}", size).into()),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size: {}", size).into())
+}
+
+// human readable size, e.g. 1.5 MiB
+pub fn format_size(size: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", size, units[0]),
+        _ => format!("{:.1} {}", value, units[unit]),
+    }
+}
+
+// $ORBITER_CACHE_MAX_SIZE, otherwise 1G
+pub fn get_cache_max_size() -> Result<u64, Box<dyn std::error::Error>> {
+    match env::var(ORBITER_CACHE_MAX_SIZE_ENV_KEY) {
+        Ok(max_size) => parse_size(&max_size),
+        Err(_) => Ok(DEFAULT_CACHE_MAX_SIZE),
+    }
+}
+
+// cached download of url, if its content is still around
+pub fn get_cache_entry(url: &str) -> Result<Option<CacheEntry>, Box<dyn std::error::Error>> {
+    Ok(get_entry_in(&get_download_cache_dir_path()?, url))
+}
+
+pub fn add_cache_entry<R: Read>(
+    url: &str,
+    file_name: &str,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    reader: &mut R,
+) -> Result<CacheEntry, Box<dyn std::error::Error>> {
+    add_entry_in(
+        &get_download_cache_dir_path()?,
+        url,
+        file_name,
+        etag,
+        last_modified,
+        reader,
+    )
+}
+
+// copies the cached content to dest, marking the entry as recently used
+pub fn copy_cache_entry(
+    entry: &mut CacheEntry,
+    dest: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cache_dir = get_download_cache_dir_path()?;
+    fs::copy(get_blob_path(&cache_dir, &entry.sha256), dest)?;
+    entry.last_used = get_now();
+    save_entry(&cache_dir, entry)
+}
+
+pub fn list_cache_entries() -> Result<Vec<CacheEntry>, Box<dyn std::error::Error>> {
+    list_entries_in(&get_download_cache_dir_path()?)
+}
+
+pub fn evict_cache() -> Result<(), Box<dyn std::error::Error>> {
+    evict_in(&get_download_cache_dir_path()?, get_cache_max_size()?)
+}
+
+pub fn clean_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let cache_dir = get_download_cache_dir_path()?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(cache_dir)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod download_cache_tests {
+    use super::*;
+
+    fn add(cache_dir: &Path, url: &str, content: &str, last_used: u64) -> CacheEntry {
+        let mut entry = add_entry_in(
+            cache_dir,
+            url,
+            "asset.tar.gz",
+            Some("\"etag\"".to_string()),
+            None,
+            &mut content.as_bytes(),
+        )
+        .unwrap();
+        entry.last_used = last_used;
+        save_entry(cache_dir, &entry).unwrap();
+        entry
+    }
+
+    #[test]
+    fn it_should_add_and_get_entries() {
+        let cache_dir = env::temp_dir().join(format!("orbiter-cache-get-{}", process::id()));
+        let entry = add(&cache_dir, "https://example.com/a", "hello", 1);
+
+        assert_eq!(
+            entry.sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(entry.size, 5);
+        assert_eq!(
+            get_entry_in(&cache_dir, "https://example.com/a"),
+            Some(entry)
+        );
+        assert_eq!(get_entry_in(&cache_dir, "https://example.com/b"), None);
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_evict_least_recently_used() {
+        let cache_dir = env::temp_dir().join(format!("orbiter-cache-evict-{}", process::id()));
+        let old = add(&cache_dir, "https://example.com/old", "0123456789", 1);
+        let shared = add(&cache_dir, "https://example.com/shared", "abcdefghij", 2);
+        let mirror = add(
+            &cache_dir,
+            "https://mirror.example.com/shared",
+            "abcdefghij",
+            3,
+        );
+
+        // the shared blob counts once, leaving no room for the oldest entry
+        evict_in(&cache_dir, 15).unwrap();
+
+        assert_eq!(get_entry_in(&cache_dir, &old.url), None);
+        assert!(!get_blob_path(&cache_dir, &old.sha256).exists());
+        assert_eq!(get_entry_in(&cache_dir, &shared.url), Some(shared));
+        assert_eq!(get_entry_in(&cache_dir, &mirror.url), Some(mirror));
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_keep_blobs_not_yet_referenced() {
+        let cache_dir = env::temp_dir().join(format!("orbiter-cache-orphan-{}", process::id()));
+        let entry = add(&cache_dir, "https://example.com/a", "hello", 1);
+        // as left by another worker between storing the blob and saving its entry
+        remove_if_exists(&get_entry_path(&cache_dir, &entry.url)).unwrap();
+
+        evict_in(&cache_dir, 0).unwrap();
+
+        assert!(get_blob_path(&cache_dir, &entry.sha256).exists());
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_parse_sizes() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("500M").unwrap(), 500 * 1024 * 1024);
+        assert_eq!(parse_size("2gib").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("M").is_err());
+    }
+}
@@ -3,9 +3,9 @@ use crate::utils::{config::ShellSpecificEvaluatable, shells::SupportedShell};
 pub fn process_evaluatable(
     current_shell: &SupportedShell,
     evaluatable: &ShellSpecificEvaluatable,
-) -> Result<(), Box<dyn std::error::Error>> {
-    match evaluatable {
-        ShellSpecificEvaluatable::Generic(generic) => println!("{}", generic),
+) -> Result<String, Box<dyn std::error::Error>> {
+    let evaluated = match evaluatable {
+        ShellSpecificEvaluatable::Generic(generic) => format!("{}\n", generic),
         ShellSpecificEvaluatable::ShellSpecific(shell_specific) => {
             let shell_specific_evaluatable = match current_shell {
                 SupportedShell::Sh => &shell_specific.sh,
@@ -16,12 +16,13 @@ pub fn process_evaluatable(
                 SupportedShell::WinCmd => &shell_specific.wincmd,
             };
 
-            match shell_specific_evaluatable {
-                Some(evaluatable) => println!("{}", evaluatable),
-                None => (),
+            if let Some(evaluatable) = shell_specific_evaluatable {
+                format!("{}\n", evaluatable)
+            } else {
+                String::new()
             }
         }
     };
 
-    Ok(())
+    Ok(evaluated)
 }
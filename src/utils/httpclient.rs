@@ -1,6 +1,8 @@
 use regex::Regex;
 
-use reqwest::{header::CONTENT_DISPOSITION, Url};
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::{header::CONTENT_DISPOSITION, StatusCode, Url};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 pub fn get_resource_name(
     response: &reqwest::blocking::Response,
@@ -19,31 +21,110 @@ pub fn get_resource_name(
     Ok(resource_name)
 }
 
+pub fn get_text(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
+    let res = error_for_status(send(client.get(url))?)?;
+
+    Ok(res.text()?)
+}
+
+// url without its query, which carries the token of providers authenticating with one
+pub fn get_redacted_url(url: &Url) -> Url {
+    let mut url = url.to_owned();
+    url.set_query(None);
+
+    url
+}
+
+// reqwest errors display the url of the request, query included
+fn redact_error(err: reqwest::Error) -> reqwest::Error {
+    match err.url().map(get_redacted_url) {
+        Some(url) => err.with_url(url),
+        None => err,
+    }
+}
+
+pub fn send(request: RequestBuilder) -> Result<Response, Box<dyn std::error::Error>> {
+    Ok(request.send().map_err(redact_error)?)
+}
+
+fn get_header(response: &Response, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| response.headers().get(*name))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+// unsuccessful responses as errors, detailing the GitHub/GitLab style rate limit if exhausted
+pub fn error_for_status(response: Response) -> Result<Response, Box<dyn std::error::Error>> {
+    let status = response.status();
+    let remaining = get_header(&response, &["x-ratelimit-remaining", "ratelimit-remaining"]);
+    let is_rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && remaining.as_deref() == Some("0"));
+    if !is_rate_limited {
+        return Ok(response.error_for_status().map_err(redact_error)?);
+    }
+
+    let mut message = format!(
+        "rate limit exceeded for {}",
+        get_redacted_url(response.url())
+    );
+    if let Some(limit) = get_header(&response, &["x-ratelimit-limit", "ratelimit-limit"]) {
+        message.push_str(&format!(
+            ", {} of {} requests left",
+            remaining.unwrap_or_else(|| "0".to_string()),
+            limit
+        ));
+    }
+    let reset = get_header(&response, &["x-ratelimit-reset", "ratelimit-reset"])
+        .and_then(|reset| reset.parse::<i64>().ok())
+        .and_then(|reset| OffsetDateTime::from_unix_timestamp(reset).ok())
+        .and_then(|reset| reset.format(&Rfc3339).ok());
+    if let Some(reset) = reset {
+        message.push_str(&format!(", resets at {}", reset));
+    } else if let Some(retry_after) = get_header(&response, &["retry-after"]) {
+        message.push_str(&format!(", retry after {}s", retry_after));
+    }
+
+    Err(message.into())
+}
+
 pub fn get_resource_name_from_url(url: &Url) -> Result<String, Box<dyn std::error::Error>> {
     let resource_name = url
         .path_segments()
-        .and_then(|segments| segments.last())
+        .and_then(|mut segments| segments.next_back())
         .and_then(|name| if name.is_empty() { None } else { Some(name) })
         .unwrap_or("tmp.bin");
 
     Ok(resource_name.to_string())
 }
 
-pub fn get_binary_pattern_by_os() -> Result<regex::Regex, Box<dyn std::error::Error>> {
-    let os = std::env::consts::OS;
-    Ok(match os {
-        "linux" => Regex::new(r"(linux|linux-gnu)")?,
-        "macos" => Regex::new(r"(darwin|mac|macos|osx|os-x)")?,
-        "windows" => Regex::new(r"(windows|cygwin|[-_]win|win64|win32)")?,
-        _ => panic!("Unsupported os: {}", os),
-    })
-}
+#[cfg(test)]
+mod redact_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_strip_tokens_from_urls() {
+        let url = Url::parse(
+            "https://gitee.com/api/v5/repos/org/tool/releases?page=2&access_token=secret",
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_redacted_url(&url).as_str(),
+            "https://gitee.com/api/v5/repos/org/tool/releases"
+        );
+    }
+
+    #[test]
+    fn it_should_strip_tokens_from_transport_errors() {
+        let client = reqwest::blocking::Client::new();
+        let err = send(client.get("http://127.0.0.1:1/releases?access_token=secret"))
+            .unwrap_err()
+            .to_string();
 
-pub fn get_binary_pattern_by_arch() -> Result<regex::Regex, Box<dyn std::error::Error>> {
-    let machine_arch = std::env::consts::ARCH;
-    Ok(match machine_arch {
-        "x86_64" | "amd64" => Regex::new(r"(x86_64|amd64|intel|linux64)")?,
-        "arm64" | "aarch64" => Regex::new(r"(arm64|aarch64)")?,
-        _ => panic!("Unsupported architecture: {}", machine_arch),
-    })
+        assert!(!err.contains("secret"));
+        assert!(err.contains("http://127.0.0.1:1/releases"));
+    }
 }
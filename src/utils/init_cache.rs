@@ -0,0 +1,308 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use sha2::{Digest, Sha256};
+
+use super::config::{ConditionValues, Payload};
+use super::paths::*;
+use super::shells::SupportedShell;
+use super::template::get_env_placeholders;
+
+// what the rendered init script depends on besides orbiter itself: the config files
+// read, and the env vars, commands and hostname consulted by `when:` conditions and
+// {env.NAME} placeholders, recorded so that a fresh cache is told without the config
+#[derive(Debug, Default, PartialEq)]
+pub struct InitCacheInputs {
+    pub config_files: Vec<PathBuf>,
+    pub env: BTreeSet<String>,
+    pub commands: BTreeSet<String>,
+    pub hostname: bool,
+}
+
+impl InitCacheInputs {
+    pub fn new(config_files: &[PathBuf], payloads: &[Payload]) -> InitCacheInputs {
+        let mut inputs = InitCacheInputs {
+            config_files: config_files.to_vec(),
+            ..InitCacheInputs::default()
+        };
+
+        for config_file in config_files {
+            let config = fs::read_to_string(config_file).unwrap_or_default();
+            inputs.env.extend(get_env_placeholders(&config));
+        }
+
+        for condition in payloads.iter().filter_map(|payload| payload.when.as_ref()) {
+            inputs.env.extend(
+                condition
+                    .env
+                    .iter()
+                    .flatten()
+                    .map(|(key, _)| key.to_owned()),
+            );
+            inputs.commands.extend(match &condition.command_exists {
+                Some(ConditionValues::Single(command)) => vec![command.to_owned()],
+                Some(ConditionValues::Multiple(commands)) => commands.to_owned(),
+                None => vec![],
+            });
+            inputs.hostname |= condition.hostname.is_some();
+        }
+
+        inputs
+    }
+
+    fn from_lines<'a>(lines: impl Iterator<Item = &'a str>) -> InitCacheInputs {
+        let mut inputs = InitCacheInputs::default();
+        for line in lines {
+            match line.split_once(' ') {
+                Some(("file", path)) => inputs.config_files.push(PathBuf::from(path)),
+                Some(("env", name)) => {
+                    inputs.env.insert(name.to_owned());
+                }
+                Some(("command", name)) => {
+                    inputs.commands.insert(name.to_owned());
+                }
+                _ if line == "hostname" => inputs.hostname = true,
+                _ => {}
+            }
+        }
+
+        inputs
+    }
+
+    fn to_lines(&self) -> Vec<String> {
+        let mut lines = self
+            .config_files
+            .iter()
+            .map(|config_file| format!("file {}", config_file.display()))
+            .collect::<Vec<String>>();
+        lines.extend(self.env.iter().map(|name| format!("env {}", name)));
+        lines.extend(self.commands.iter().map(|name| format!("command {}", name)));
+        if self.hostname {
+            lines.push("hostname".to_string());
+        }
+
+        lines
+    }
+}
+
+// fingerprint of a file or dir, changing whenever it is modified
+fn get_modified_fingerprint(path: &Path) -> String {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| modified.as_nanos())
+                .unwrap_or_default();
+
+            format!("{}:{}:{}", path.display(), modified, metadata.len())
+        }
+        Err(_) => format!("{}:missing", path.display()),
+    }
+}
+
+// keyed on orbiter version, shell, config files, installed payloads and host inputs
+pub fn get_init_cache_key(
+    current_shell: &SupportedShell,
+    inputs: &InitCacheInputs,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(current_shell.as_program_str());
+    hasher.update(get_modified_fingerprint(&get_config_path()?));
+    hasher.update(get_modified_fingerprint(&get_conf_d_dir_path()?));
+    for config_file in &inputs.config_files {
+        // the dir changes when files matching an include glob come and go
+        hasher.update(get_modified_fingerprint(config_file));
+        hasher.update(get_modified_fingerprint(&get_dir(config_file)?));
+    }
+    hasher.update(get_modified_fingerprint(&get_payloads_dir_path()?));
+
+    for name in &inputs.env {
+        hasher.update(format!("env {}={:?}", name, env::var_os(name)));
+    }
+    for command in &inputs.commands {
+        hasher.update(format!(
+            "command {}={:?}",
+            command,
+            which::which(command).ok()
+        ));
+    }
+    if inputs.hostname {
+        hasher.update(format!("hostname {:?}", hostname::get().ok()));
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// key file holds the cache key, followed by the inputs it was computed from
+fn get_init_cache_key_path(cache_path: &Path) -> PathBuf {
+    let mut key_file_name = cache_path.file_name().unwrap_or_default().to_owned();
+    key_file_name.push(".key");
+
+    cache_path.with_file_name(key_file_name)
+}
+
+// path of the cached init script if it is still up to date
+pub fn get_fresh_init_cache(
+    current_shell: &SupportedShell,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let cache_path = get_init_cache_path(current_shell)?;
+    if !cache_path.exists() {
+        return Ok(None);
+    }
+
+    let key_file = fs::read_to_string(get_init_cache_key_path(&cache_path)).unwrap_or_default();
+    let mut lines = key_file.lines();
+    let cached_key = lines.next().unwrap_or_default();
+    let inputs = InitCacheInputs::from_lines(lines);
+    if cached_key != get_init_cache_key(current_shell, &inputs)? {
+        return Ok(None);
+    }
+
+    Ok(Some(cache_path))
+}
+
+pub fn save_init_cache(
+    current_shell: &SupportedShell,
+    script: &str,
+    inputs: &InitCacheInputs,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let cache_path = get_init_cache_path(current_shell)?;
+    let key_path = get_init_cache_key_path(&cache_path);
+    fs::create_dir_all(get_dir(&cache_path)?)?;
+
+    // drop the key first so an interrupted write is never taken as fresh
+    if key_path.exists() {
+        fs::remove_file(&key_path)?;
+    }
+
+    // write through a temp file as other shells may be sourcing the cache
+    let tmp_path = cache_path.with_file_name(format!(
+        "{}.{}.tmp",
+        cache_path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id()
+    ));
+    fs::write(&tmp_path, script)?;
+    fs::rename(&tmp_path, &cache_path)?;
+    let mut key_file = get_init_cache_key(current_shell, inputs)?;
+    for line in inputs.to_lines() {
+        key_file.push_str(&format!("\n{}", line));
+    }
+    fs::write(&key_path, key_file)?;
+
+    Ok(cache_path)
+}
+
+// invalidate the cached init scripts of all shells
+pub fn clear_init_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let cache_dir = get_home_dir_path()?.join(DEFAULT_ORBITER_INIT_CACHE_HOME);
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod init_cache_key_tests {
+    use super::*;
+
+    fn get_payload(when: &str) -> Payload {
+        serde_yaml::from_str(&format!(
+            "{{ id: fd, resource: https://example.com/fd.tar.gz, when: {} }}",
+            when
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_round_trip_inputs_through_the_key_file() {
+        let inputs = InitCacheInputs::new(
+            &[PathBuf::from("/tmp/orbiter missing config.yml")],
+            &[get_payload(
+                "{ env: { WORK: }, command_exists: [git, fd], hostname: 'work-*' }",
+            )],
+        );
+        let lines = inputs.to_lines();
+
+        assert_eq!(
+            lines,
+            vec![
+                "file /tmp/orbiter missing config.yml",
+                "env WORK",
+                "command fd",
+                "command git",
+                "hostname"
+            ]
+        );
+        assert_eq!(
+            InitCacheInputs::from_lines(lines.iter().map(String::as_str)),
+            inputs
+        );
+    }
+
+    #[test]
+    fn it_should_miss_when_an_env_condition_changes() {
+        let inputs = InitCacheInputs::new(
+            &[],
+            &[get_payload(
+                "{ env: { ORBITER_TEST_INIT_CACHE_VAR: work } }",
+            )],
+        );
+
+        env::set_var("ORBITER_TEST_INIT_CACHE_VAR", "work");
+        let work_key = get_init_cache_key(&SupportedShell::Zsh, &inputs).unwrap();
+        env::set_var("ORBITER_TEST_INIT_CACHE_VAR", "home");
+        let home_key = get_init_cache_key(&SupportedShell::Zsh, &inputs).unwrap();
+        env::remove_var("ORBITER_TEST_INIT_CACHE_VAR");
+
+        assert_ne!(work_key, home_key);
+        assert_eq!(
+            get_init_cache_key(&SupportedShell::Zsh, &InitCacheInputs::default()).unwrap(),
+            get_init_cache_key(&SupportedShell::Zsh, &InitCacheInputs::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_miss_when_a_config_file_changes() {
+        let dir = env::temp_dir().join(format!("orbiter-init-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("config.yml");
+        fs::write(&config_file, "- id: fd\n  resource: sharkdp/fd\n").unwrap();
+        let inputs = InitCacheInputs::new(&[config_file.to_owned()], &[]);
+
+        let key = get_init_cache_key(&SupportedShell::Bash, &inputs).unwrap();
+        assert_eq!(
+            get_init_cache_key(&SupportedShell::Bash, &inputs).unwrap(),
+            key
+        );
+        assert_ne!(
+            get_init_cache_key(&SupportedShell::Zsh, &inputs).unwrap(),
+            key
+        );
+
+        fs::write(
+            &config_file,
+            "- id: fd\n  resource: sharkdp/fd\n  src: fd.zsh\n",
+        )
+        .unwrap();
+        assert_ne!(
+            get_init_cache_key(&SupportedShell::Bash, &inputs).unwrap(),
+            key
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_keep_the_key_next_to_the_cache() {
+        assert_eq!(
+            get_init_cache_key_path(Path::new("/home/user/.orbiter/init/init.zsh")),
+            PathBuf::from("/home/user/.orbiter/init/init.zsh.key")
+        );
+    }
+}
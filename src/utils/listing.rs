@@ -1,12 +1,27 @@
+use super::condition::get_skip_reasons;
 use super::config::Payload;
+use super::shells::SupportedShell;
 
 pub fn get_listing(
     payloads: &[Payload],
     scope: &ListingScope,
+    current_shell: &SupportedShell,
 ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let skip_reasons = get_skip_reasons(payloads, current_shell);
+
     Ok(match scope {
-        ListingScope::Effective => payloads.iter().map(|p| p.id.to_owned()).collect(),
-        ListingScope::All => payloads.iter().map(|p| p.id.to_owned()).collect(),
+        ListingScope::Effective => payloads
+            .iter()
+            .filter(|p| !skip_reasons.contains_key(&p.id))
+            .map(|p| p.id.to_owned())
+            .collect(),
+        ListingScope::All => payloads
+            .iter()
+            .map(|p| match skip_reasons.get(&p.id) {
+                Some(reason) => format!("{} (skipped: {})", &p.id, reason),
+                None => p.id.to_owned(),
+            })
+            .collect(),
     })
 }
 
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::paths::get_lock_path;
+use super::platform::{get_arch, get_os};
+
+// resolved source of a payload, recorded at install time so that the same
+// config reproduces the same binaries on another machine
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+// pins of a payload: a commit holds on every platform, whereas release and
+// download assets differ per platform and are pinned under <os>-<arch>
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockEntry {
+    #[serde(flatten)]
+    pub source: LockedPayload,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<String, LockedPayload>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub payloads: BTreeMap<String, LockEntry>,
+}
+
+// os-arch of this host, e.g. linux-x86_64
+pub fn get_platform_key() -> String {
+    format!("{}-{}", get_os(), get_arch())
+}
+
+impl Lockfile {
+    // pin for this host, none if only other platforms were pinned
+    pub fn get_pinned(&self, payload_id: &str) -> Option<&LockedPayload> {
+        self.get_pinned_for(payload_id, &get_platform_key())
+    }
+
+    fn get_pinned_for(&self, payload_id: &str, platform: &str) -> Option<&LockedPayload> {
+        let entry = self.payloads.get(payload_id)?;
+        match &entry.source.commit {
+            Some(_) => Some(&entry.source),
+            // an asset pinned without a platform may belong to any of them
+            None => entry.platforms.get(platform),
+        }
+    }
+
+    // returns whether the lock entry changed
+    pub fn pin_payload(&mut self, payload_id: &str, locked: LockedPayload) -> bool {
+        self.pin_payload_for(payload_id, &get_platform_key(), locked)
+    }
+
+    fn pin_payload_for(&mut self, payload_id: &str, platform: &str, locked: LockedPayload) -> bool {
+        if self.get_pinned_for(payload_id, platform) == Some(&locked) {
+            return false;
+        }
+
+        let entry = self.payloads.entry(payload_id.to_owned()).or_default();
+        if locked.commit.is_some() {
+            *entry = LockEntry {
+                source: locked,
+                ..LockEntry::default()
+            };
+        } else {
+            entry.source = LockedPayload::default();
+            entry.platforms.insert(platform.to_owned(), locked);
+        }
+        true
+    }
+}
+
+pub fn from_reader(reader: &mut dyn Read) -> Result<Lockfile, Box<dyn std::error::Error>> {
+    Ok(serde_yaml::from_reader(reader)?)
+}
+
+pub fn get_lockfile() -> Result<Lockfile, Box<dyn std::error::Error>> {
+    let lock_path = get_lock_path()?;
+    if !lock_path.exists() {
+        return Ok(Lockfile::default());
+    }
+
+    let file = File::open(lock_path)?;
+    let mut reader = BufReader::new(file);
+    from_reader(&mut reader)
+}
+
+pub fn save_lockfile(lockfile: &Lockfile) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(get_lock_path()?)?;
+    serde_yaml::to_writer(BufWriter::new(file), lockfile)?;
+
+    Ok(())
+}
+
+pub fn unpin_payload(payload_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lockfile = get_lockfile()?;
+    if lockfile.payloads.remove(payload_id).is_some() {
+        save_lockfile(&lockfile)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod from_reader_tests {
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn it_should_parse_from_reader() {
+        let lock = r#"
+        payloads:
+          neovim:
+            platforms:
+              linux-x86_64:
+                tag: v0.9.5
+                url: https://github.com/neovim/neovim/releases/download/v0.9.5/nvim-linux64.tar.gz
+                sha256: 44ee7d7a7c3e2ae9b3d1f5c2c0f8f0b7b8b0d2a7e51b5b4bdbf4b8c5a2d9f1e3
+          zsh-autosuggestions:
+            commit: c3d4e576c9c86eac62884bd47c01f6faed043fc5
+        "#;
+
+        let mut bufreader = BufReader::new(lock.as_bytes());
+
+        let actual = from_reader(&mut bufreader).unwrap();
+        let neovim = actual.get_pinned_for("neovim", "linux-x86_64").unwrap();
+        let autosuggestions = actual
+            .get_pinned_for("zsh-autosuggestions", "macos-aarch64")
+            .unwrap();
+
+        assert_eq!(neovim.tag.as_deref(), Some("v0.9.5"));
+        assert_eq!(neovim.commit, None);
+        assert_eq!(
+            autosuggestions.commit.as_deref(),
+            Some("c3d4e576c9c86eac62884bd47c01f6faed043fc5")
+        );
+        assert_eq!(autosuggestions.url, None);
+        assert_eq!(actual.get_pinned_for("neovim", "macos-aarch64"), None);
+    }
+}
+
+#[cfg(test)]
+mod pin_payload_tests {
+    use super::*;
+
+    fn asset(url: &str) -> LockedPayload {
+        LockedPayload {
+            tag: Some("v1.0.0".to_string()),
+            url: Some(url.to_string()),
+            commit: None,
+            sha256: Some("00".repeat(32)),
+        }
+    }
+
+    #[test]
+    fn it_should_pin_assets_per_platform() {
+        let mut lockfile = Lockfile::default();
+        let linux = asset("https://example.com/tool-linux-x86_64.tar.gz");
+        let macos = asset("https://example.com/tool-darwin-arm64.tar.gz");
+
+        assert!(lockfile.pin_payload_for("tool", "linux-x86_64", linux.clone()));
+        assert!(lockfile.pin_payload_for("tool", "macos-aarch64", macos.clone()));
+        assert!(!lockfile.pin_payload_for("tool", "linux-x86_64", linux.clone()));
+
+        assert_eq!(
+            lockfile.get_pinned_for("tool", "linux-x86_64"),
+            Some(&linux)
+        );
+        assert_eq!(
+            lockfile.get_pinned_for("tool", "macos-aarch64"),
+            Some(&macos)
+        );
+        assert_eq!(lockfile.get_pinned_for("tool", "linux-aarch64"), None);
+    }
+
+    #[test]
+    fn it_should_ignore_assets_pinned_without_platform() {
+        let lock = r#"
+        payloads:
+          tool:
+            url: https://example.com/tool-linux-x86_64.tar.gz
+            sha256: 44ee7d7a7c3e2ae9b3d1f5c2c0f8f0b7b8b0d2a7e51b5b4bdbf4b8c5a2d9f1e3
+        "#;
+        let mut lockfile = from_reader(&mut lock.as_bytes()).unwrap();
+
+        assert_eq!(lockfile.get_pinned_for("tool", "linux-x86_64"), None);
+
+        // re-pinning moves the asset under the platform
+        let linux = asset("https://example.com/tool-linux-x86_64.tar.gz");
+        assert!(lockfile.pin_payload_for("tool", "linux-x86_64", linux.clone()));
+        let entry = lockfile.payloads.get("tool").unwrap();
+        assert_eq!(entry.source, LockedPayload::default());
+        assert_eq!(entry.platforms.get("linux-x86_64"), Some(&linux));
+    }
+}
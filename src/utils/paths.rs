@@ -14,7 +14,11 @@ pub const DEFAULT_ORBITER_PAYLOADS_CURRENT_INSTALL_HOME: &str = "current";
 pub const DEFAULT_ORBITER_DASHBOARD_HOME: &str = "dashboard";
 pub const DEFAULT_ORBITER_DASHBOARD_BIN_HOME: &str = "bin";
 pub const DEFAULT_ORBITER_CONFIG_FILENAME: &str = ".orbiter.config.yml";
+pub const DEFAULT_ORBITER_LOCK_FILENAME: &str = "orbiter.lock";
 pub const DEFAULT_ORBITER_PAYLOAD_CONFIG_DIR: &str = ".__orbiter__";
+pub const DEFAULT_ORBITER_PAYLOAD_STATE_FILENAME: &str = "state.json";
+pub const DEFAULT_ORBITER_INIT_CACHE_HOME: &str = "init";
+pub const DEFAULT_ORBITER_DOWNLOAD_CACHE_HOME: &str = "cache";
 
 pub const ORBITER_CONFIG_ENV_KEY: &str = "ORBITER_CONFIG";
 pub const ORBITER_HOME_ENV_KEY: &str = "ORBITER_HOME";
@@ -40,6 +44,23 @@ pub fn get_config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     })
 }
 
+// ~/.config/orbiter/conf.d
+pub fn get_conf_d_dir_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+        _ => dirs::home_dir()
+            .ok_or("unable to locate home dir")?
+            .join(".config"),
+    };
+
+    Ok(config_home.join("orbiter").join("conf.d"))
+}
+
+// orbiter.lock, next to the config file
+pub fn get_lock_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_config_path()?.with_file_name(DEFAULT_ORBITER_LOCK_FILENAME))
+}
+
 // .orbiter/
 
 fn get_dflt_home_dir_path() -> PathBuf {
@@ -60,11 +81,18 @@ pub fn get_home_dir_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     })
 }
 
+// .orbiter/payloads
+pub fn get_payloads_dir_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_home_dir_path()?.join(DEFAULT_ORBITER_PAYLOADS_HOME))
+}
+
 // .orbiter/payloads/<payload id>
 pub fn get_payload_dir_path(payload: &Payload) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    Ok(get_home_dir_path()?
-        .join(DEFAULT_ORBITER_PAYLOADS_HOME)
-        .join(&payload.id))
+    get_payload_dir_path_by_id(&payload.id)
+}
+
+pub fn get_payload_dir_path_by_id(payload_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_payloads_dir_path()?.join(payload_id))
 }
 
 // .orbiter/payloads/<payload id>/current
@@ -86,6 +114,27 @@ pub fn get_payload_config_dir_path(
     Ok(payload_config_dir)
 }
 
+// .orbiter/payloads/<payload id>/.__orbiter__/state.json
+pub fn get_payload_state_path(payload_id: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_payload_dir_path_by_id(payload_id)?
+        .join(DEFAULT_ORBITER_PAYLOAD_CONFIG_DIR)
+        .join(DEFAULT_ORBITER_PAYLOAD_STATE_FILENAME))
+}
+
+// .orbiter/init/init.<shell>
+pub fn get_init_cache_path(
+    current_shell: &SupportedShell,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_home_dir_path()?
+        .join(DEFAULT_ORBITER_INIT_CACHE_HOME)
+        .join(format!("init.{}", current_shell.as_program_str())))
+}
+
+// .orbiter/cache
+pub fn get_download_cache_dir_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(get_home_dir_path()?.join(DEFAULT_ORBITER_DOWNLOAD_CACHE_HOME))
+}
+
 pub fn get_bin_dir_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let bin_path = get_home_dir_path()?
         .join(DEFAULT_ORBITER_DASHBOARD_HOME)
@@ -100,16 +149,28 @@ pub fn get_bin_file_path(bin_fname: &str) -> Result<PathBuf, Box<dyn std::error:
     Ok(bin_file_path)
 }
 
-pub fn resolve_single_path(file_path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+// resolve a path or glob relative to the given dir
+pub fn resolve_single_path(
+    current_dir: &Path,
+    file_path: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     if file_path.contains("*") {
-        let entry = glob(&file_path)?
+        let pattern = if Path::new(file_path).is_absolute() {
+            file_path.to_string()
+        } else {
+            Path::new(&glob::Pattern::escape(&current_dir.display().to_string()))
+                .join(file_path)
+                .display()
+                .to_string()
+        };
+        let entry = glob(&pattern)?
             .next()
             .ok_or(format!("unable to locate {}", file_path))??;
 
         return Ok(fs::canonicalize(&entry)?);
     }
 
-    Ok(fs::canonicalize(file_path)?)
+    Ok(fs::canonicalize(current_dir.join(file_path))?)
 }
 
 pub fn get_file_name(func: &str) -> Result<String, Box<dyn std::error::Error>> {
@@ -121,52 +182,26 @@ pub fn get_file_name(func: &str) -> Result<String, Box<dyn std::error::Error>> {
     Ok(String::from(file_name))
 }
 
-pub fn get_dir(bin_path: &PathBuf) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let mut bin_path = bin_path.clone();
+pub fn get_dir(bin_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut bin_path = bin_path.to_path_buf();
     bin_path.pop(); // get parent dir path
 
     Ok(bin_path)
 }
 
-pub fn update_path(current_shell: &SupportedShell) {
-    match current_shell {
-        SupportedShell::Sh => export_path_sh(),
-        SupportedShell::Bash => export_path_sh(),
-        SupportedShell::Zsh => export_path_sh(),
-        SupportedShell::Fish => export_path_fish(),
-        SupportedShell::PowerShell => export_path_powershell(),
-        SupportedShell::WinCmd => export_path_wincmd(),
-    }
-}
-
-fn export_path_sh() {
-    // update PATH with orbiter dashboard bin dir
-    println!(
-        "export PATH=\"{}:$PATH\"",
-        get_bin_dir_path().unwrap().display()
-    );
-}
-
-fn export_path_fish() {
-    // update PATH with orbiter dashboard bin dir
-    println!(
-        "set -x PATH \"{}\" $PATH",
-        get_bin_dir_path().unwrap().display()
-    );
-}
+// shell code prepending the orbiter dashboard bin dir to PATH
+pub fn get_path_export(
+    current_shell: &SupportedShell,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let bin_dir_path = get_bin_dir_path()?;
+    let bin_dir = bin_dir_path.display();
 
-fn export_path_powershell() {
-    // update PATH with orbiter dashboard bin dir
-    println!(
-        "$env:PATH = \"{};$env:PATH\"",
-        get_bin_dir_path().unwrap().display()
-    );
-}
-
-fn export_path_wincmd() {
-    // update PATH with orbiter dashboard bin dir
-    println!(
-        "setx PATH \"{};%PATH%\"",
-        get_bin_dir_path().unwrap().display()
-    );
+    Ok(match current_shell {
+        SupportedShell::Sh | SupportedShell::Bash | SupportedShell::Zsh => {
+            format!("export PATH=\"{}:$PATH\"\n", bin_dir)
+        }
+        SupportedShell::Fish => format!("set -x PATH \"{}\" $PATH\n", bin_dir),
+        SupportedShell::PowerShell => format!("$env:PATH = \"{};$env:PATH\"\n", bin_dir),
+        SupportedShell::WinCmd => format!("setx PATH \"{};%PATH%\"\n", bin_dir),
+    })
 }
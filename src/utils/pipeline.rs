@@ -1,116 +1,406 @@
 use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
 
 use super::config::*;
+use super::lockfile::{LockedPayload, Lockfile};
 use super::paths::*;
+use super::script::ShellOutput;
 use super::shells::SupportedShell;
 use super::shim::*;
+use super::state::*;
 use super::symlink::*;
 use crate::hooks::extract::*;
 use crate::hooks::init::*;
 use crate::hooks::install::*;
+use crate::hooks::lazy::*;
 use crate::hooks::load::*;
 use crate::hooks::resource::*;
 use crate::hooks::src::*;
 
 use log::info;
+use log::warn;
 
-pub fn process_payload(
+// the state manifest is written last, so it only exists once an install has finished
+pub fn is_payload_installed(payload: &Payload) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(get_payload_state_path(&payload.id)?.exists()
+        && get_payload_current_install_dir_path(payload)?.exists())
+}
+
+// lazy payloads are installed by their shim on first use
+pub fn is_lazy_payload(payload: &Payload) -> bool {
+    matches!(
+        &payload.exec,
+        Some(Executable::Command {
+            lazy: Some(true),
+            ..
+        })
+    )
+}
+
+pub fn create_lazy_shim(
+    current_shell: &SupportedShell,
+    payload: &Payload,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    if let Some(Executable::Command { run, alias, .. }) = &payload.exec {
+        let cmd = alias.as_ref().unwrap_or(run);
+        let shim_content = get_lazy_shim(current_shell, &payload.id, cmd)?;
+
+        return Ok(Some(create_shim(current_shell, cmd, &shim_content)?));
+    }
+
+    Ok(None)
+}
+
+fn record_hook(state: &mut PayloadState, payload: &Payload, hook: &str, result: &ShellOutput) {
+    if let Some(exit_code) = result.exit_code {
+        if exit_code != 0 {
+            warn!(
+                "{} hook of payload {} exited with {}: {}",
+                hook, &payload.id, exit_code, &result.output
+            );
+        }
+    }
+
+    state.hooks.insert(hook.to_owned(), result.exit_code);
+}
+
+// fetch, extract, install and shim the payload if not already installed
+// returns the lock entry of the payload if it has been freshly installed
+pub fn install_payload(
     current_shell: &SupportedShell,
     payload: &Payload,
-) -> Result<(), Box<dyn std::error::Error>> {
+    pinned: Option<&LockedPayload>,
+    is_locked: bool,
+) -> Result<Option<LockedPayload>, Box<dyn std::error::Error>> {
     // check if already worked on
+    if is_payload_installed(payload)? {
+        return Ok(None);
+    }
+
+    if is_locked && pinned.is_none() {
+        return Err(format!("payload {} is not pinned in the lockfile", &payload.id).into());
+    }
+
     let payload_orbiter_dir_path = get_payload_config_dir_path(payload)?;
-    if !payload_orbiter_dir_path.exists()
-        || !get_payload_current_install_dir_path(payload)?.exists()
-    {
-        info!(
-            "Creating payload config directory {}",
-            payload_orbiter_dir_path.to_str().unwrap()
-        );
-        fs::create_dir_all(&payload_orbiter_dir_path)?;
+    info!(
+        "Creating payload config directory {}",
+        payload_orbiter_dir_path.to_str().unwrap()
+    );
+    fs::create_dir_all(&payload_orbiter_dir_path)?;
 
-        let init_result = if let Some(init_cmd) = &payload.init {
-            Some(init(current_shell, init_cmd)?)
-        } else {
-            None
-        };
+    let mut state = PayloadState::new(payload)?;
 
-        // save resource
-        let resource_path = get_adaptive_resource(payload, init_result.as_deref())?;
+    let init_result = if let Some(init_cmd) = &payload.init {
+        let init_result = init(current_shell, init_cmd, &payload_orbiter_dir_path)?;
+        record_hook(&mut state, payload, "init", &init_result);
+        Some(init_result.output)
+    } else {
+        None
+    };
 
-        // set wd to payload current dir
-        let current_install_dir = get_payload_current_install_dir_path(payload)?;
-        assert!(std::env::set_current_dir(&current_install_dir).is_ok());
+    // start over from what an unfinished install left behind, and discard a partial,
+    // unverified or refused resource on failure
+    let current_install_dir = get_payload_current_install_dir_path(payload)?;
+    if current_install_dir.exists() {
+        fs::remove_dir_all(&current_install_dir)?;
+    }
 
-        // extract resource
-        if let Some(extract_cmd) = &payload.extract {
-            extract(current_shell, &extract_cmd)?;
-        } else if let Some(asset_path) = &resource_path {
-            extract_asset(current_shell, &asset_path)?;
-        }
+    let result = get_adaptive_resource(payload, init_result.as_deref(), pinned)
+        .and_then(|fetched| install_fetched(current_shell, payload, fetched, state));
+    if result.is_err() && current_install_dir.exists() {
+        fs::remove_dir_all(&current_install_dir)?;
+    }
 
-        // install resource
-        if let Some(install_cmd) = &payload.install {
-            install(current_shell, install_cmd)?;
-        }
+    result.map(Some)
+}
 
-        // create shim
-        if let Some(exec) = &payload.exec {
-            match exec {
-                Executable::Run(cmd) => {
-                    create_shim(
-                        current_shell,
-                        &cmd,
-                        &get_shim_content(current_shell, &cmd, &cmd, None)?,
-                    )?;
-                }
+// extract, install and shim a fetched resource, then record the install
+fn install_fetched(
+    current_shell: &SupportedShell,
+    payload: &Payload,
+    fetched: FetchedResource,
+    mut state: PayloadState,
+) -> Result<LockedPayload, Box<dyn std::error::Error>> {
+    let resource_path = fetched.asset_path;
+
+    // hooks and shims work off the payload current dir
+    let current_install_dir = get_payload_current_install_dir_path(payload)?;
+    fs::create_dir_all(&current_install_dir)?;
+
+    // extract resource
+    if let Some(extract_cmd) = &payload.extract {
+        let extract_result = extract(current_shell, extract_cmd, &current_install_dir)?;
+        record_hook(&mut state, payload, "extract", &extract_result);
+    } else if let Some(asset_path) = &resource_path {
+        extract_asset(
+            current_shell,
+            asset_path,
+            &current_install_dir,
+            payload.strip_components.unwrap_or(0),
+        )?;
+    }
+
+    // install resource
+    if let Some(install_cmd) = &payload.install {
+        let install_result = install(current_shell, install_cmd, &current_install_dir)?;
+        record_hook(&mut state, payload, "install", &install_result);
+    }
+
+    // create shim
+    if let Some(exec) = &payload.exec {
+        let bin_path = match exec {
+            Executable::Run(cmd) => Some(create_shim(
+                current_shell,
+                cmd,
+                &get_shim_content(current_shell, &current_install_dir, cmd, cmd, None)?,
+            )?),
 
-                Executable::Command {
-                    run,
-                    alias,
-                    use_symlink,
-                } => {
-                    if let Some(is_use_symlink) = use_symlink {
-                        if is_use_symlink.to_owned() {
-                            create_symlink(current_shell, run, alias)?;
-                        }
+            Executable::Command {
+                run,
+                alias,
+                use_symlink,
+                ..
+            } => {
+                if let Some(is_use_symlink) = use_symlink {
+                    if is_use_symlink.to_owned() {
+                        Some(create_symlink(
+                            current_shell,
+                            &current_install_dir,
+                            run,
+                            alias,
+                        )?)
                     } else {
-                        if let Some(alias) = alias.as_ref() {
-                            create_shim(
-                                current_shell,
-                                alias,
-                                &get_shim_content(current_shell, run, alias, None)?,
-                            )?;
-                        } else {
-                            create_shim(
-                                current_shell,
-                                run,
-                                &get_shim_content(current_shell, run, run, None)?,
-                            )?;
-                        };
-                    };
+                        None
+                    }
+                } else if let Some(alias) = alias.as_ref() {
+                    Some(create_shim(
+                        current_shell,
+                        alias,
+                        &get_shim_content(current_shell, &current_install_dir, run, alias, None)?,
+                    )?)
+                } else {
+                    Some(create_shim(
+                        current_shell,
+                        run,
+                        &get_shim_content(current_shell, &current_install_dir, run, run, None)?,
+                    )?)
                 }
-            };
+            }
         };
-    }
+
+        if let Some(bin_path) = bin_path {
+            state.bins.push(bin_path.display().to_string());
+        }
+    };
+
+    state.version = fetched
+        .locked
+        .tag
+        .to_owned()
+        .or(fetched.locked.commit.to_owned());
+    state.source = fetched.locked.to_owned();
+    save_payload_state(&payload.id, &state)?;
+
+    Ok(fetched.locked)
+}
+
+pub type InstallResult = Result<Option<LockedPayload>, String>;
+
+// install payloads across `jobs` workers, returning the results in payload order;
+// payloads are expected in dependency order and wait for their dependencies to finish
+pub fn install_payloads_concurrently(
+    current_shell: &SupportedShell,
+    payloads: &[&Payload],
+    lockfile: &Lockfile,
+    is_locked: bool,
+    jobs: usize,
+    on_installed: &(dyn Fn(&Payload, &InstallResult) + Sync),
+) -> Vec<InstallResult> {
+    let install = |payload: &Payload| {
+        let pinned = lockfile.get_pinned(&payload.id);
+        install_payload(current_shell, payload, pinned, is_locked).map_err(|err| err.to_string())
+    };
+
+    run_in_dependency_order(payloads, jobs, &install, on_installed)
+}
+
+fn run_in_dependency_order(
+    payloads: &[&Payload],
+    jobs: usize,
+    install: &(dyn Fn(&Payload) -> InstallResult + Sync),
+    on_installed: &(dyn Fn(&Payload, &InstallResult) + Sync),
+) -> Vec<InstallResult> {
+    let next_payload = AtomicUsize::new(0);
+    let results = payloads
+        .iter()
+        .map(|_| Mutex::new(Ok(None)))
+        .collect::<Vec<Mutex<InstallResult>>>();
+
+    // indexes of the dependencies of each payload, along with whether each install succeeded
+    let dependencies = payloads
+        .iter()
+        .enumerate()
+        .map(|(index, payload)| {
+            payload
+                .depends_on
+                .iter()
+                .flatten()
+                .filter_map(|id| payloads.iter().position(|p| &p.id == id))
+                .filter(|position| *position < index)
+                .collect::<Vec<usize>>()
+        })
+        .collect::<Vec<Vec<usize>>>();
+    let finished = Mutex::new(vec![None; payloads.len()]);
+    let finished_changed = Condvar::new();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, payloads.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next_payload.fetch_add(1, Ordering::SeqCst);
+                let payload = match payloads.get(index) {
+                    Some(payload) => payload,
+                    None => break,
+                };
+
+                let failed_dependency = {
+                    let mut finished = finished.lock().unwrap();
+                    while dependencies[index].iter().any(|d| finished[*d].is_none()) {
+                        finished = finished_changed.wait(finished).unwrap();
+                    }
+
+                    dependencies[index]
+                        .iter()
+                        .find(|d| finished[**d] == Some(false))
+                        .map(|d| &payloads[*d].id)
+                };
+
+                let result = match failed_dependency {
+                    Some(dependency_id) => Err(format!(
+                        "dependency {} of payload {} failed to install",
+                        dependency_id, &payload.id
+                    )),
+                    None => install(payload),
+                };
+                on_installed(payload, &result);
+
+                let is_installed = result.is_ok();
+                *results[index].lock().unwrap() = result;
+                finished.lock().unwrap()[index] = Some(is_installed);
+                finished_changed.notify_all();
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.into_inner().unwrap())
+        .collect()
+}
+
+// shell code sourcing and loading an installed payload
+pub fn render_payload(
+    current_shell: &SupportedShell,
+    payload: &Payload,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let current_install_dir = get_payload_current_install_dir_path(payload)?;
+    let mut rendered = String::new();
 
     // source scripts
     if let Some(src_target) = &payload.src {
-        // set wd to payload config dir
-        let current_install_dir = get_payload_current_install_dir_path(payload)?;
-        assert!(std::env::set_current_dir(&current_install_dir).is_ok());
-
-        src(current_shell, src_target)?;
+        rendered.push_str(&src(current_shell, src_target, &current_install_dir)?);
     }
 
     // post load
     if let Some(load_cmd) = &payload.load {
-        // set wd to payload config dir
-        let current_install_dir = get_payload_current_install_dir_path(payload)?;
-        assert!(std::env::set_current_dir(&current_install_dir).is_ok());
+        rendered.push_str(&load(current_shell, load_cmd)?);
+    }
+
+    // defer or trigger on demand
+    Ok(lazy(
+        current_shell,
+        &payload.id,
+        &rendered,
+        payload.defer.unwrap_or(false),
+        &payload.trigger,
+    ))
+}
+
+#[cfg(test)]
+mod run_in_dependency_order_tests {
+    use super::*;
+
+    fn get_payload(id: &str, depends_on: &[&str]) -> Payload {
+        serde_yaml::from_str(&format!(
+            "{{ id: {}, resource: org/{}, depends_on: [{}] }}",
+            id,
+            id,
+            depends_on.join(", ")
+        ))
+        .unwrap()
+    }
 
-        load(current_shell, load_cmd)?;
+    #[test]
+    fn it_should_wait_for_dependencies() {
+        let payloads = [
+            get_payload("a", &[]),
+            get_payload("b", &["a"]),
+            get_payload("c", &[]),
+            get_payload("d", &["b", "c"]),
+        ];
+        let installed = Mutex::new(vec![]);
+        let install = |payload: &Payload| {
+            // give dependents the chance to run ahead
+            if payload.id == "a" {
+                thread::sleep(std::time::Duration::from_millis(50));
+            }
+            installed.lock().unwrap().push(payload.id.to_owned());
+            Ok(None)
+        };
+
+        let results = run_in_dependency_order(
+            &payloads.iter().collect::<Vec<&Payload>>(),
+            4,
+            &install,
+            &|_, _| {},
+        );
+
+        assert!(results.iter().all(Result::is_ok));
+        let installed = installed.into_inner().unwrap();
+        let position = |id: &str| installed.iter().position(|i| i == id).unwrap();
+        assert!(position("a") < position("b"));
+        assert!(position("b") < position("d"));
+        assert!(position("c") < position("d"));
     }
 
-    Ok(())
+    #[test]
+    fn it_should_fail_dependents_of_failed_payloads() {
+        let payloads = [
+            get_payload("a", &[]),
+            get_payload("b", &["a"]),
+            get_payload("c", &[]),
+        ];
+        let install = |payload: &Payload| match payload.id.as_str() {
+            "a" => Err("no asset found".to_string()),
+            _ => Ok(None),
+        };
+
+        let results = run_in_dependency_order(
+            &payloads.iter().collect::<Vec<&Payload>>(),
+            2,
+            &install,
+            &|_, _| {},
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                Err("no asset found".to_string()),
+                Err("dependency a of payload b failed to install".to_string()),
+                Ok(None),
+            ]
+        );
+    }
 }
@@ -0,0 +1,53 @@
+use std::env;
+
+// config key of the current os, as used for os specific resources and commands
+pub fn get_os() -> &'static str {
+    env::consts::OS
+}
+
+// config key of the current arch, naming 32 bit and big/little endian variants apart
+pub fn get_arch() -> &'static str {
+    match env::consts::ARCH {
+        "x86" => "i686",
+        "arm" => "armv7",
+        "powerpc64" if cfg!(target_endian = "little") => "ppc64le",
+        "powerpc64" => "ppc64",
+        arch => arch,
+    }
+}
+
+// config key of an os as commonly named
+pub fn normalize_os(os: &str) -> String {
+    match os.to_lowercase().as_str() {
+        "darwin" | "osx" => "macos".to_string(),
+        os => os.to_string(),
+    }
+}
+
+// config key of an arch as commonly named, such as amd64 or armhf
+pub fn normalize_arch(arch: &str) -> String {
+    match arch.to_lowercase().as_str() {
+        "amd64" | "x64" => "x86_64".to_string(),
+        "arm64" => "aarch64".to_string(),
+        "x86" | "i386" | "i586" | "386" => "i686".to_string(),
+        "arm" | "armv7l" | "armhf" => "armv7".to_string(),
+        "riscv64gc" => "riscv64".to_string(),
+        "powerpc64le" | "ppc64el" => "ppc64le".to_string(),
+        arch => arch.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod normalize_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_normalize_to_config_keys() {
+        assert_eq!(normalize_arch("AMD64"), "x86_64");
+        assert_eq!(normalize_arch("armhf"), "armv7");
+        assert_eq!(normalize_arch("ppc64el"), "ppc64le");
+        assert_eq!(normalize_arch("s390x"), "s390x");
+        assert_eq!(normalize_os("Darwin"), "macos");
+        assert_eq!(normalize_arch(get_arch()), get_arch());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::hooks::resource::get_platform_resource;
+use crate::providers::asset::rank_assets;
+use crate::providers::release::get_repo_release;
+
+use super::config::{Payload, Resource};
+use super::platform::{get_arch, get_os};
+
+// release and ranked assets the payload resolves to on this host, the selected one marked
+pub fn get_resolution(payload: &Payload) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let repo = match get_platform_resource(&payload.resource) {
+        Some(Resource::Repo(repo)) if repo.from_release.unwrap_or(false) => repo,
+        Some(_) => {
+            return Ok(vec![format!(
+                "{} is not installed from a release, nothing to resolve",
+                &payload.id
+            )])
+        }
+        None => {
+            return Ok(vec![format!(
+                "{} has no resource for {} {}",
+                &payload.id,
+                get_os(),
+                get_arch()
+            )])
+        }
+    };
+
+    let release = get_repo_release(repo)?;
+    let candidates = rank_assets(&release, repo)?;
+    let mut lines = vec![format!(
+        "{} {} for {} {}:",
+        &repo.repo,
+        &release.tag_name,
+        get_os(),
+        get_arch()
+    )];
+    for (index, candidate) in candidates.iter().enumerate() {
+        let marker = if index == 0 && candidate.rejection.is_none() {
+            "*"
+        } else {
+            " "
+        };
+        lines.push(format!("{} {}", marker, candidate));
+    }
+    if candidates
+        .first()
+        .is_none_or(|candidate| candidate.rejection.is_some())
+    {
+        lines.push("no compatible asset, set binary_pattern to pick one".to_string());
+    }
+
+    Ok(lines)
+}
@@ -1,10 +1,12 @@
 // run shell script in subshell
 
 use std::error::Error;
+use std::path::Path;
 use std::process::{Command, Output};
 use std::str;
 
 use super::config::{OSSpecificCommand, SupportedShellSpecificCommand};
+use super::platform::get_os;
 use super::shells::SupportedShell;
 
 pub fn run_cmd(program: &str, args: &[&str]) -> Result<Output, Box<dyn std::error::Error>> {
@@ -32,65 +34,78 @@ pub fn run_cmd_in_shell(
         .output()?)
 }
 
-#[allow(dead_code)]
+// run in the given working dir rather than the process wide one
+pub fn run_cmd_in_shell_in_dir(
+    current_shell: &SupportedShell,
+    full_cmd: &str,
+    current_dir: &Path,
+) -> Result<Output, Box<dyn std::error::Error>> {
+    Ok(Command::new(current_shell.as_program_str())
+        .arg(current_shell.as_dflt_arg_str())
+        .arg(full_cmd)
+        .current_dir(current_dir)
+        .output()?)
+}
+
+#[derive(Debug, Default)]
+pub struct ShellOutput {
+    pub output: String,
+    pub exit_code: Option<i32>, // none if nothing ran for the current shell/os
+}
+
 pub fn run_cmd_in_shell_with_output(
     current_shell: &SupportedShell,
     full_cmd: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let proc = run_cmd_in_shell(current_shell, full_cmd)?;
+    current_dir: &Path,
+) -> Result<ShellOutput, Box<dyn std::error::Error>> {
+    let proc = run_cmd_in_shell_in_dir(current_shell, full_cmd, current_dir)?;
 
     let stdout_content = str::from_utf8(&proc.stdout).unwrap_or("");
     let stderr_content = str::from_utf8(&proc.stderr).unwrap_or("");
 
     let output = format!("{}{}", stdout_content, stderr_content);
 
-    Ok(output)
+    Ok(ShellOutput {
+        output,
+        exit_code: proc.status.code(),
+    })
 }
 
 pub fn run_os_specific_shell_cmd(
     current_shell: &SupportedShell,
     op_os_specific_cmd: &Option<OSSpecificCommand>,
-) -> Result<String, Box<dyn Error>> {
+    current_dir: &Path,
+) -> Result<ShellOutput, Box<dyn Error>> {
     Ok(if let Some(all_os_specific_cmd) = op_os_specific_cmd {
         match all_os_specific_cmd {
-            OSSpecificCommand::Generic(cmd) => run_cmd_in_shell_with_output(current_shell, cmd)?,
+            OSSpecificCommand::Generic(cmd) => {
+                run_cmd_in_shell_with_output(current_shell, cmd, current_dir)?
+            }
             OSSpecificCommand::OSSpecific(os_specific_cmd) => {
-                let os = std::env::consts::OS;
-                match os {
-                    "linux" => {
-                        if let Some(cmd) = &os_specific_cmd.linux {
-                            run_cmd_in_shell_with_output(current_shell, cmd)?
-                        } else {
-                            "".to_owned()
-                        }
-                    }
-                    "macos" => {
-                        if let Some(cmd) = &os_specific_cmd.macos {
-                            run_cmd_in_shell_with_output(current_shell, cmd)?
-                        } else {
-                            "".to_owned()
-                        }
-                    }
-                    "windows" => {
-                        if let Some(cmd) = &os_specific_cmd.windows {
-                            run_cmd_in_shell_with_output(current_shell, cmd)?
-                        } else {
-                            "".to_owned()
-                        }
-                    }
-                    _ => "".to_owned(),
+                let cmd = match get_os() {
+                    "linux" => &os_specific_cmd.linux,
+                    "macos" => &os_specific_cmd.macos,
+                    "windows" => &os_specific_cmd.windows,
+                    "freebsd" => &os_specific_cmd.freebsd,
+                    "netbsd" => &os_specific_cmd.netbsd,
+                    _ => &None,
+                };
+                match cmd {
+                    Some(cmd) => run_cmd_in_shell_with_output(current_shell, cmd, current_dir)?,
+                    None => ShellOutput::default(),
                 }
             }
         }
     } else {
-        "".to_owned()
+        ShellOutput::default()
     })
 }
 
 pub fn run_shell_specific_cmd(
     current_shell: &SupportedShell,
     shell_specific_cmd: &SupportedShellSpecificCommand,
-) -> Result<String, Box<dyn std::error::Error>> {
+    current_dir: &Path,
+) -> Result<ShellOutput, Box<dyn std::error::Error>> {
     let os_specific_cmd = match current_shell {
         SupportedShell::Sh => &shell_specific_cmd.sh,
         SupportedShell::Bash => &shell_specific_cmd.bash,
@@ -100,5 +115,5 @@ pub fn run_shell_specific_cmd(
         SupportedShell::WinCmd => &shell_specific_cmd.wincmd,
     };
 
-    run_os_specific_shell_cmd(current_shell, os_specific_cmd)
+    run_os_specific_shell_cmd(current_shell, os_specific_cmd, current_dir)
 }
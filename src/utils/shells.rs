@@ -34,6 +34,30 @@ impl SupportedShell {
         }
     }
 
+    // shell code sourcing the given script
+    pub fn get_source_cmd(&self, script_path: &std::path::Path) -> String {
+        match self {
+            SupportedShell::Sh | SupportedShell::Zsh | SupportedShell::Bash => {
+                format!(". \"{}\"", script_path.display())
+            }
+            SupportedShell::Fish => format!("source \"{}\"", script_path.display()),
+            SupportedShell::PowerShell => format!(". \"{}\"", script_path.display()),
+            SupportedShell::WinCmd => format!("call \"{}\"", script_path.display()),
+        }
+    }
+
+    // shell of the current user, falling back to sh
+    pub fn from_env() -> SupportedShell {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        let shell_name = std::path::Path::new(&shell)
+            .file_name()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("sh");
+
+        SupportedShell::from_str(shell_name)
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(shell: &str) -> SupportedShell {
         match shell {
             "sh" => SupportedShell::Sh,
@@ -1,7 +1,7 @@
 use std::fs;
 use std::fs::File;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::utils::paths::*;
 use crate::utils::script::*;
@@ -9,29 +9,31 @@ use crate::utils::script::*;
 use super::shells::SupportedShell;
 
 pub fn get_func_name(func: &str) -> Result<String, Box<dyn std::error::Error>> {
-    Ok(get_file_name(func)?)
+    get_file_name(func)
 }
 
 pub fn get_shim_content(
     current_shell: &SupportedShell,
+    current_dir: &Path,
     func: &str,
     bin_dir: &str,
     env: Option<&str>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     match env {
-        None => get_basic_shim(current_shell, func, bin_dir),
-        Some("base") => get_basic_shim(current_shell, func, bin_dir),
-        Some(&_) => get_basic_shim(current_shell, func, bin_dir),
+        None => get_basic_shim(current_shell, current_dir, func, bin_dir),
+        Some("base") => get_basic_shim(current_shell, current_dir, func, bin_dir),
+        Some(&_) => get_basic_shim(current_shell, current_dir, func, bin_dir),
     }
 }
 
 pub fn get_basic_shim(
     current_shell: &SupportedShell,
+    current_dir: &Path,
     func: &str,
     bin_dir: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let func_name = get_func_name(func)?;
-    let resolved_bin_path = resolve_single_path(bin_dir)?;
+    let resolved_bin_path = resolve_single_path(current_dir, bin_dir)?;
 
     // set exec mode
     run_cmd_in_shell(
@@ -62,17 +64,46 @@ pub fn get_basic_shim(
     ))
 }
 
+// shim installing the payload on first use, then running the shim or symlink replacing it
+pub fn get_lazy_shim(
+    current_shell: &SupportedShell,
+    payload_id: &str,
+    func: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let func_name = get_func_name(func)?;
+    let orbiter_path = std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| "orbiter".to_string());
+
+    Ok(format!(
+        r##"#!/bin/sh
+
+if [ "$__ORBITER_LAZY_INSTALL" = "{id}" ]; then
+    echo "orbiter: {func} is still unavailable after installing {id}" >&2
+    exit 1
+fi
+
+"{orbiter}" install --shell {shell} "{id}" >&2 || exit 1
+__ORBITER_LAZY_INSTALL="{id}" exec "$0" "$@"
+"##,
+        id = payload_id,
+        func = func_name,
+        orbiter = orbiter_path,
+        shell = current_shell.as_program_str(),
+    ))
+}
+
 fn get_shim_path(cmd: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    Ok(get_bin_file_path(&get_func_name(&cmd)?)?)
+    get_bin_file_path(&get_func_name(cmd)?)
 }
 
 pub fn create_shim(
     current_shell: &SupportedShell,
     cmd: &str,
     shim_content: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     fs::create_dir_all(&get_bin_dir_path()?)?;
-    let shim_path = get_shim_path(&cmd)?;
+    let shim_path = get_shim_path(cmd)?;
     let mut dest = File::create(&shim_path)?;
     io::copy(&mut shim_content.as_bytes(), &mut dest)?;
 
@@ -82,9 +113,41 @@ pub fn create_shim(
         &format!("chmod +x {}", &shim_path.display().to_string()),
     )?;
 
-    Ok(())
+    Ok(shim_path)
 }
 
 pub fn remove_shim(cmd: &str) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(fs::remove_file(get_shim_path(&cmd)?)?)
+    Ok(fs::remove_file(get_shim_path(cmd)?)?)
+}
+
+#[cfg(test)]
+mod lazy_shim_tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn it_should_install_the_payload_before_running() {
+        let shim = get_lazy_shim(&SupportedShell::Bash, "fd-find", "**/fd").unwrap();
+
+        assert!(shim.starts_with("#!/bin/sh\n"));
+        assert!(shim.contains(" install --shell bash \"fd-find\" >&2 || exit 1\n"));
+        assert!(shim.contains("__ORBITER_LAZY_INSTALL=\"fd-find\" exec \"$0\" \"$@\"\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_should_not_loop_when_the_install_leaves_the_shim() {
+        let shim = get_lazy_shim(&SupportedShell::Bash, "fd", "fd").unwrap();
+        let output = Command::new("sh")
+            .args(["-c", &shim, "fd"])
+            .env("__ORBITER_LAZY_INSTALL", "fd")
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "orbiter: fd is still unavailable after installing fd\n"
+        );
+    }
 }
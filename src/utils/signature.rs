@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::Path;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use p256::ecdsa::signature::Verifier;
+use p256::pkcs8::DecodePublicKey;
+
+// public keys can either be inlined in the config or point to a key file
+fn read_public_key(public_key: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let public_key = public_key.trim();
+    let key_path = match (public_key.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rel_path), Some(home_dir)) => home_dir.join(rel_path),
+        _ => Path::new(public_key).to_path_buf(),
+    };
+
+    if key_path.is_file() {
+        Ok(fs::read_to_string(key_path)?)
+    } else {
+        Ok(public_key.to_string())
+    }
+}
+
+pub fn verify_minisign(
+    file_path: &Path,
+    public_key: &str,
+    signature: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let public_key = read_public_key(public_key)?;
+    let public_key = if public_key.trim().lines().count() > 1 {
+        // minisign.pub, with its untrusted comment line
+        minisign_verify::PublicKey::decode(public_key.trim())?
+    } else {
+        minisign_verify::PublicKey::from_base64(public_key.trim())?
+    };
+    let signature = minisign_verify::Signature::decode(signature)?;
+
+    public_key.verify(&fs::read(file_path)?, &signature, false)?;
+
+    Ok(())
+}
+
+// cosign `sign-blob` signatures: base64 encoded ECDSA P-256 over the file
+pub fn verify_cosign(
+    file_path: &Path,
+    public_key: &str,
+    signature: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let public_key = p256::ecdsa::VerifyingKey::from_public_key_pem(&read_public_key(public_key)?)?;
+    let signature = p256::ecdsa::Signature::from_der(&BASE64_STANDARD.decode(signature.trim())?)?;
+
+    public_key.verify(&fs::read(file_path)?, &signature)?;
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::config::Payload;
+use super::lockfile::LockedPayload;
+use super::paths::get_payload_state_path;
+
+// what orbiter did when installing a payload, kept in .__orbiter__/state.json
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PayloadState {
+    pub version: Option<String>,
+    pub source: LockedPayload,
+    pub installed_at: String,
+    pub config_hash: String,
+    pub bins: Vec<String>,                    // shims and symlinks created
+    pub hooks: BTreeMap<String, Option<i32>>, // exit code per hook run
+}
+
+impl PayloadState {
+    pub fn new(payload: &Payload) -> Result<PayloadState, Box<dyn std::error::Error>> {
+        Ok(PayloadState {
+            installed_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+            config_hash: get_config_hash(payload)?,
+            ..PayloadState::default()
+        })
+    }
+}
+
+// unset options are left out so that new config keys do not change existing hashes
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(strip_nulls).collect())
+        }
+        _ => value,
+    }
+}
+
+pub fn get_config_hash(payload: &Payload) -> Result<String, Box<dyn std::error::Error>> {
+    let config = serde_json::to_vec(&strip_nulls(serde_json::to_value(payload)?))?;
+
+    Ok(hex::encode(Sha256::digest(config)))
+}
+
+// whether the payload config changed since it was installed
+pub fn is_config_drifted(payload: &Payload) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(match get_payload_state(&payload.id)? {
+        Some(state) => state.config_hash != get_config_hash(payload)?,
+        None => false,
+    })
+}
+
+fn read_payload_state(
+    state_path: &Path,
+) -> Result<Option<PayloadState>, Box<dyn std::error::Error>> {
+    if !state_path.exists() {
+        return Ok(None);
+    }
+
+    let reader = BufReader::new(File::open(state_path)?);
+
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+fn write_payload_state(
+    state_path: &Path,
+    state: &PayloadState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let writer = BufWriter::new(File::create(state_path)?);
+    serde_json::to_writer_pretty(writer, state)?;
+
+    Ok(())
+}
+
+pub fn get_payload_state(
+    payload_id: &str,
+) -> Result<Option<PayloadState>, Box<dyn std::error::Error>> {
+    read_payload_state(&get_payload_state_path(payload_id)?)
+}
+
+pub fn save_payload_state(
+    payload_id: &str,
+    state: &PayloadState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_payload_state(&get_payload_state_path(payload_id)?, state)
+}
+
+#[cfg(test)]
+mod config_hash_tests {
+    use super::*;
+
+    fn get_payload(install: Option<&str>) -> Payload {
+        serde_yaml::from_str(&format!(
+            "{{ id: fd, resource: {{ repo: sharkdp/fd, from_release: true }}, install: {} }}",
+            install.map_or("null".to_string(), |i| format!("'{}'", i))
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_should_ignore_unset_options() {
+        let payload = get_payload(None);
+        let expected = serde_json::json!({
+            "id": "fd",
+            "resource": { "repo": "sharkdp/fd", "from_release": true },
+        });
+
+        assert_eq!(
+            get_config_hash(&payload).unwrap(),
+            hex::encode(Sha256::digest(serde_json::to_vec(&expected).unwrap()))
+        );
+    }
+
+    #[test]
+    fn it_should_change_with_config() {
+        assert_ne!(
+            get_config_hash(&get_payload(None)).unwrap(),
+            get_config_hash(&get_payload(Some("chmod +x fd"))).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod payload_state_tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn it_should_round_trip_state() {
+        let dir = env::temp_dir().join(format!("orbiter-state-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let state_path = dir.join("state.json");
+        assert_eq!(read_payload_state(&state_path).unwrap(), None);
+
+        let payload = serde_yaml::from_str("{ id: fd, resource: sharkdp/fd }").unwrap();
+        let state = PayloadState {
+            version: Some("v9.0.0".to_string()),
+            source: LockedPayload {
+                tag: Some("v9.0.0".to_string()),
+                url: Some("https://example.com/fd.tar.gz".to_string()),
+                ..LockedPayload::default()
+            },
+            bins: vec!["/home/user/.orbiter/bin/fd".to_string()],
+            hooks: BTreeMap::from([("install".to_string(), Some(0)), ("src".to_string(), None)]),
+            ..PayloadState::new(&payload).unwrap()
+        };
+        write_payload_state(&state_path, &state).unwrap();
+
+        assert_eq!(read_payload_state(&state_path).unwrap(), Some(state));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,30 +1,40 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
 use super::paths::*;
 use super::script::*;
 use super::shells::SupportedShell;
 
 pub fn create_symlink(
     current_shell: &SupportedShell,
+    current_dir: &Path,
     file_path: &str,
     alias: &Option<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let file_name = if let Some(alias) = alias {
         alias.to_string()
     } else {
         get_file_name(file_path)?
     };
 
-    let resolved_bin_path = resolve_single_path(file_path)?;
+    let resolved_bin_path = resolve_single_path(current_dir, file_path)?;
     // set exec mode
     run_cmd_in_shell(
         current_shell,
         &format!("chmod +x {}", &resolved_bin_path.display().to_string()),
     )?;
 
-    println!(
-        "ln -sf {} {}",
-        &resolved_bin_path.display().to_string(),
-        get_bin_file_path(&file_name)?.display().to_string()
-    );
+    // ln -sf
+    fs::create_dir_all(get_bin_dir_path()?)?;
+    let bin_file_path = get_bin_file_path(&file_name)?;
+    if bin_file_path.symlink_metadata().is_ok() {
+        fs::remove_file(&bin_file_path)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&resolved_bin_path, &bin_file_path)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(&resolved_bin_path, &bin_file_path)?;
 
-    Ok(())
+    Ok(bin_file_path)
 }
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use regex::{Captures, Regex};
+
+use crate::providers::version::is_version_req;
+
+use super::config::Payload;
+use super::paths::get_payload_current_install_dir_path;
+use super::platform::{get_arch, get_os};
+
+// payload fields whose strings are interpolated, leaving the likes of id and when as is
+const INTERPOLATED_FIELDS: [&str; 8] = [
+    "resource", "init", "extract", "install", "update", "src", "load", "exec",
+];
+
+fn get_builtin_vars(
+    payload: &Payload,
+    version: Option<&str>,
+) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    let mut vars = BTreeMap::new();
+    // named like the os and arch config keys
+    vars.insert("os".to_string(), get_os().to_string());
+    vars.insert("arch".to_string(), get_arch().to_string());
+    vars.insert("id".to_string(), payload.id.to_owned());
+    vars.insert(
+        "payload_dir".to_string(),
+        get_payload_current_install_dir_path(payload)?
+            .display()
+            .to_string(),
+    );
+    if let Some(home) = dirs::home_dir() {
+        vars.insert("home".to_string(), home.display().to_string());
+    }
+    if let Some(version) = version {
+        vars.insert("version".to_string(), version.to_string());
+    }
+
+    Ok(vars)
+}
+
+fn get_placeholder_regex() -> Regex {
+    Regex::new(r"(\$?)\{([A-Za-z_][A-Za-z0-9_]*(?:\.[A-Za-z0-9_]+)?)\}").unwrap()
+}
+
+// names of the env vars read by {env.NAME} placeholders
+pub fn get_env_placeholders(text: &str) -> Vec<String> {
+    get_placeholder_regex()
+        .captures_iter(text)
+        .filter(|captures| captures[1].is_empty())
+        .filter_map(|captures| captures[2].strip_prefix("env.").map(str::to_owned))
+        .collect()
+}
+
+// replace {name} and {env.NAME} placeholders, leaving unknown ones such as {init} and
+// shell parameter expansions such as ${name} untouched
+pub fn interpolate(text: &str, vars: &BTreeMap<String, String>) -> String {
+    get_placeholder_regex()
+        .replace_all(text, |captures: &Captures| {
+            let name = &captures[2];
+            if !captures[1].is_empty() {
+                return captures[0].to_string();
+            }
+
+            match name.strip_prefix("env.") {
+                Some(env_name) => env::var(env_name).unwrap_or_default(),
+                None => vars
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| captures[0].to_string()),
+            }
+        })
+        .to_string()
+}
+
+fn is_var_used(value: &serde_json::Value, name: &str) -> bool {
+    match value {
+        serde_json::Value::String(text) => get_placeholder_regex()
+            .captures_iter(text)
+            .any(|captures| captures[1].is_empty() && &captures[2] == name),
+        serde_json::Value::Array(values) => values.iter().any(|value| is_var_used(value, name)),
+        serde_json::Value::Object(map) => map.values().any(|value| is_var_used(value, name)),
+        _ => false,
+    }
+}
+
+fn interpolate_value(value: &mut serde_json::Value, vars: &BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::String(text) => *text = interpolate(text, vars),
+        serde_json::Value::Array(values) => {
+            for value in values {
+                interpolate_value(value, vars);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                interpolate_value(value, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+// interpolate built-in and user defined vars into the payload, with built-in vars
+// taking precedence and user defined vars able to refer to built-in ones
+pub fn interpolate_payload(
+    payload: Payload,
+    user_vars: &BTreeMap<String, String>,
+) -> Result<Payload, Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(&payload)?;
+    let ver = value["resource"]["ver"].as_str().map(str::to_owned);
+
+    // a range has no single version to render until the release is resolved
+    let version = match ver.as_deref() {
+        Some(ver) if is_version_req(ver) => {
+            if INTERPOLATED_FIELDS
+                .iter()
+                .any(|field| is_var_used(&value[field], "version"))
+            {
+                return Err(format!(
+                    "payload {} refers to {{version}}, but its ver {} is a version range",
+                    &payload.id, ver
+                )
+                .into());
+            }
+            None
+        }
+        ver => ver,
+    };
+
+    let builtin_vars = get_builtin_vars(&payload, version)?;
+    let mut vars = user_vars
+        .iter()
+        .map(|(name, value)| (name.to_owned(), interpolate(value, &builtin_vars)))
+        .collect::<BTreeMap<String, String>>();
+    vars.extend(builtin_vars);
+
+    if let serde_json::Value::Object(map) = &mut value {
+        for field in INTERPOLATED_FIELDS {
+            if let Some(value) = map.get_mut(field) {
+                interpolate_value(value, &vars);
+            }
+        }
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod interpolate_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_interpolate_known_vars_only() {
+        let vars = BTreeMap::from([
+            ("os".to_string(), "linux".to_string()),
+            ("version".to_string(), "8.7.0".to_string()),
+        ]);
+
+        assert_eq!(
+            interpolate(
+                "https://example.com/fd-v{version}-{os}.tar.gz?{init}&{unknown}",
+                &vars
+            ),
+            "https://example.com/fd-v8.7.0-linux.tar.gz?{init}&{unknown}"
+        );
+        assert_eq!(
+            interpolate("f() { echo ${os} {os}; }", &vars),
+            "f() { echo ${os} linux; }"
+        );
+    }
+
+    #[test]
+    fn it_should_interpolate_payloads() {
+        let payload: Payload = serde_yaml::from_str(
+            r#"
+            id: fd
+            resource: https://example.com/fd-{target}.tar.gz
+            exec: "**/{id}"
+            depends_on: ["{id}"]
+            "#,
+        )
+        .unwrap();
+        let user_vars = BTreeMap::from([("target".to_string(), "{arch}-{os}".to_string())]);
+
+        let actual =
+            serde_json::to_value(interpolate_payload(payload, &user_vars).unwrap()).unwrap();
+
+        assert_eq!(
+            actual["resource"],
+            format!("https://example.com/fd-{}-{}.tar.gz", get_arch(), get_os())
+        );
+        assert_eq!(actual["exec"], "**/fd");
+        assert_eq!(actual["depends_on"][0], "{id}");
+    }
+
+    #[test]
+    fn it_should_refuse_version_placeholders_with_ranges() {
+        let get_payload = |ver: &str| -> Payload {
+            serde_yaml::from_str(&format!(
+                r#"
+                id: fd
+                resource:
+                  repo: sharkdp/fd
+                  ver: "{}"
+                install: echo {{version}}
+                "#,
+                ver
+            ))
+            .unwrap()
+        };
+
+        let payload = interpolate_payload(get_payload("v10.2.0"), &BTreeMap::new()).unwrap();
+        assert_eq!(
+            serde_json::to_value(payload).unwrap()["install"],
+            "echo v10.2.0"
+        );
+
+        assert_eq!(
+            interpolate_payload(get_payload("^10"), &BTreeMap::new())
+                .unwrap_err()
+                .to_string(),
+            "payload fd refers to {version}, but its ver ^10 is a version range"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::Path;
+
+use log::info;
+
+use super::{
+    lockfile::unpin_payload,
+    paths::{get_bin_dir_path, get_payload_dir_path_by_id},
+    state::get_payload_state,
+};
+
+// whether content names payload_dir itself or a path inside it, rather than a dir
+// merely sharing its prefix, such as payloads/fd-find for payloads/fd
+fn contains_dir(content: &str, payload_dir: &Path) -> bool {
+    let payload_dir = payload_dir.display().to_string();
+    content.match_indices(&payload_dir).any(|(index, _)| {
+        content[index + payload_dir.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| matches!(c, '/' | '\\' | '"' | '\'') || c.is_whitespace())
+    })
+}
+
+// shims embed the payload bin dir, symlinks point into it
+fn is_payload_bin(bin_path: &Path, payload_dir: &Path) -> bool {
+    match fs::read_link(bin_path) {
+        Ok(target) => target.starts_with(payload_dir),
+        Err(_) => fs::read_to_string(bin_path)
+            .map(|content| contains_dir(&content, payload_dir))
+            .unwrap_or(false),
+    }
+}
+
+pub fn remove_recorded_bins(payload_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let state = match get_payload_state(payload_id)? {
+        Some(state) => state,
+        None => return Ok(false),
+    };
+
+    for bin in &state.bins {
+        let bin_path = Path::new(bin);
+        if bin_path.symlink_metadata().is_ok() {
+            info!("Removing {}", bin);
+            fs::remove_file(bin_path)?;
+        }
+    }
+
+    Ok(true)
+}
+
+pub fn remove_payload_bins(payload_dir: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let bin_dir = get_bin_dir_path()?;
+    let mut removed = vec![];
+    if !bin_dir.exists() {
+        return Ok(removed);
+    }
+
+    for entry in fs::read_dir(bin_dir)? {
+        let bin_path = entry?.path();
+        if is_payload_bin(&bin_path, payload_dir) {
+            info!("Removing {}", bin_path.display());
+            fs::remove_file(&bin_path)?;
+            removed.push(bin_path.display().to_string());
+        }
+    }
+
+    Ok(removed)
+}
+
+pub fn uninstall_payload(payload_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let payload_dir = get_payload_dir_path_by_id(payload_id)?;
+    if !payload_dir.exists() {
+        return Err(format!("Payload {} is not installed", payload_id).into());
+    }
+
+    // 1. remove shims and symlinks, recorded at install or left from older installs
+    remove_recorded_bins(payload_id)?;
+    remove_payload_bins(&fs::canonicalize(&payload_dir)?)?;
+
+    // 2. remove payload files
+    fs::remove_dir_all(&payload_dir)?;
+
+    // 3. unpin
+    unpin_payload(payload_id)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod is_payload_bin_tests {
+    use super::*;
+
+    #[test]
+    fn it_should_not_match_payloads_sharing_an_id_prefix() {
+        let dir = std::env::temp_dir().join(format!(
+            "orbiter_is_payload_bin_tests_{}",
+            std::process::id()
+        ));
+        let (fd_dir, fd_find_dir) = (dir.join("payloads/fd"), dir.join("payloads/fd-find"));
+        fs::create_dir_all(&dir).unwrap();
+
+        let fd_shim = dir.join("fd");
+        fs::write(
+            &fd_shim,
+            format!("#!/bin/sh\n\"{}/current/fd\" \"$@\"\n", fd_dir.display()),
+        )
+        .unwrap();
+        let fd_find_shim = dir.join("fdfind");
+        fs::write(
+            &fd_find_shim,
+            format!(
+                "#!/bin/sh\n\"{}/current/fdfind\" \"$@\"\n",
+                fd_find_dir.display()
+            ),
+        )
+        .unwrap();
+
+        assert!(is_payload_bin(&fd_shim, &fd_dir));
+        assert!(!is_payload_bin(&fd_find_shim, &fd_dir));
+        assert!(is_payload_bin(&fd_find_shim, &fd_find_dir));
+
+        #[cfg(unix)]
+        {
+            let fd_find_link = dir.join("fdfind-link");
+            std::os::unix::fs::symlink(fd_find_dir.join("current/fdfind"), &fd_find_link).unwrap();
+            assert!(!is_payload_bin(&fd_find_link, &fd_dir));
+            assert!(is_payload_bin(&fd_find_link, &fd_find_dir));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,21 +1,55 @@
 use std::{env, fs::File, path::Path};
 
-use time::{format_description, OffsetDateTime};
+use time::{format_description, format_description::well_known::Rfc3339, OffsetDateTime};
 
-use crate::{providers::github::get_repo_release_asset_url, utils::config::Repo};
+use reqwest::header::ACCEPT;
+
+use crate::{
+    hooks::resource::get_platform_resource,
+    providers::release::{
+        get_provider_request, get_repo_release_asset, get_repo_release_asset_url,
+    },
+    utils::config::{Repo, Resource},
+};
 
 use super::{
     config::{Executable, Payload},
-    httpclient::get_resource_name,
+    httpclient::{error_for_status, get_resource_name, send},
+    lockfile::unpin_payload,
     paths::{get_payload_current_install_dir_path, get_payload_dir_path},
     shim::remove_shim,
+    state::get_payload_state,
+    uninstall::remove_recorded_bins,
 };
 
 const ARCHIVE_DIR_DATETIME_FORMAT: &str = "[year]-[month]-[day]_[hour]:[minute]:[second]";
 
+// whether the release selected for the payload differs from the installed one or,
+// as with republished nightlies, was published since; other payloads are always updated
+pub fn is_update_available(payload: &Payload) -> Result<bool, Box<dyn std::error::Error>> {
+    match get_platform_resource(&payload.resource) {
+        Some(Resource::Repo(repo)) if repo.from_release.unwrap_or(false) => {
+            let state = match get_payload_state(&payload.id)? {
+                Some(state) => state,
+                None => return Ok(true),
+            };
+            let asset = get_repo_release_asset(repo)?;
+            let installed_at = OffsetDateTime::parse(&state.installed_at, &Rfc3339).ok();
+            let is_republished = match (asset.published_at, installed_at) {
+                (Some(published_at), Some(installed_at)) => published_at > installed_at,
+                _ => false,
+            };
+
+            Ok(state.version.as_deref() != Some(asset.tag_name.as_str()) || is_republished)
+        }
+        _ => Ok(true),
+    }
+}
+
 pub fn update_payload(payload: &Payload) -> Result<(), Box<dyn std::error::Error>> {
-    // 1. remove shim
-    if let Some(exec) = &payload.exec {
+    // 1. remove shim, as recorded at install or derived from exec for older installs
+    let is_removed = remove_recorded_bins(&payload.id)?;
+    if let (false, Some(exec)) = (is_removed, &payload.exec) {
         match exec {
             Executable::Run(cmd) => {
                 remove_shim(cmd)?;
@@ -30,29 +64,40 @@ pub fn update_payload(payload: &Payload) -> Result<(), Box<dyn std::error::Error
         };
     };
 
-    // 2. rename current folder
+    // 2. unpin so the next install resolves afresh
+    unpin_payload(&payload.id)?;
+
+    // 3. rename current folder
     Ok(std::fs::rename(
         get_payload_current_install_dir_path(payload)?,
         get_payload_dir_path(payload)?.join(format!(
             "archive_{}",
-            OffsetDateTime::now_local()?
-                .format(&format_description::parse(ARCHIVE_DIR_DATETIME_FORMAT)?)?
+            OffsetDateTime::now_local()?.format(&format_description::parse_borrowed::<1>(
+                ARCHIVE_DIR_DATETIME_FORMAT
+            )?)?
         )),
     )?)
 }
 
 fn update_orbiter_executable(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
     let orbiter_repo = Repo {
         repo: "orbiter-rs/orbiter".to_string(),
         provider: None,
+        host: None,
+        token_env: None,
         ver: None,
+        tag_pattern: None,
+        channel: None,
+        libc: None,
         from_release: Some(true),
         binary_pattern: None,
+        checksum: None,
+        signature: None,
     };
-    let res = client
-        .get(get_repo_release_asset_url(&orbiter_repo)?)
-        .send()?;
+    let res = error_for_status(send(
+        get_provider_request(&orbiter_repo, &get_repo_release_asset_url(&orbiter_repo)?)?
+            .header(ACCEPT, "application/octet-stream"),
+    )?)?;
     let mut dest = {
         let file_name = get_resource_name(&res)?;
         let file_path = dir.join(file_name);